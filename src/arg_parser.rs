@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use clap::{Arg, ArgMatches, Command};
+
+use paper::cli::config::Config;
+use paper::{MarkdownOptions, PaperError, SiteConfig, SiteConfigBuilder};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Represent the parsed arguments from CLI, on top of the ones of the config file
+pub struct ArgParser {
+    config: SiteConfig,
+}
+
+impl ArgParser {
+    /// Create a new `ArgParser` with parsed CLI arguments.
+    /// Options that are not given fall back to the ones of the config file.
+    /// Invalid arguments exit with clap's usage error
+    pub fn new() -> Result<ArgParser, PaperError> {
+        let matches = Command::new("Paper")
+            .version(VERSION)
+            .author("Andrew N. <hey@andrewnt.dev>")
+            .about("Generate static site")
+            .arg(
+                Arg::new("input")
+                    .multiple_values(true)
+                    .short('i')
+                    .long("input")
+                    .value_name("FILE")
+                    .help("Path to file(s)"),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("FILE")
+                    .help("Path to output file"),
+            )
+            .arg(
+                Arg::new("stylesheet")
+                    .multiple_values(true)
+                    .short('s')
+                    .long("stylesheet")
+                    .value_name("URL or FILE")
+                    .help("Link to stylesheet(s)"),
+            )
+            .arg(
+                Arg::new("config")
+                    .short('c')
+                    .long("config")
+                    .value_name("FILE")
+                    .help("Path to config file, paper.toml by default"),
+            )
+            .arg(
+                Arg::new("template")
+                    .short('t')
+                    .long("template")
+                    .value_name("FILE")
+                    .help("HTML template for pages without a layout"),
+            )
+            .arg(
+                Arg::new("theme")
+                    .long("theme")
+                    .value_name("DIR")
+                    .help("Theme dir with layouts, partials and assets"),
+            )
+            .args(switch(
+                "safe",
+                "no-safe",
+                "Escape raw HTML in Markdown, except for an allowlist of tags",
+                "Keep raw HTML in Markdown as it is",
+            ))
+            .args(switch(
+                "tables",
+                "no-tables",
+                "Render GitHub-flavored Markdown pipe tables",
+                "Leave pipe tables as text",
+            ))
+            .args(switch(
+                "strikethrough",
+                "no-strikethrough",
+                "Render ~~text~~ as strikethrough",
+                "Leave ~~text~~ as text",
+            ))
+            .args(switch(
+                "tasklists",
+                "no-tasklists",
+                "Render list items starting with [ ] or [x] as tasks",
+                "Leave [ ] and [x] in list items as text",
+            ))
+            .args(switch(
+                "autolinks",
+                "no-autolinks",
+                "Turn bare www. and http(s):// URLs into links",
+                "Leave bare URLs as text",
+            ))
+            .args(switch(
+                "footnotes",
+                "no-footnotes",
+                "Render [^label] footnotes at the end of the page",
+                "Leave [^label] footnotes as text",
+            ))
+            .args(switch(
+                "anchors",
+                "no-anchors",
+                "Add a ¶ permalink after each heading",
+                "Leave out the ¶ permalinks",
+            ))
+            .args(switch(
+                "toc",
+                "no-toc",
+                "Add a table of contents of the headings to Markdown pages",
+                "Leave out the table of contents",
+            ))
+            .arg(
+                Arg::new("fail-fast")
+                    .long("fail-fast")
+                    .help("Stop at the first page that fails, instead of reporting all of them"),
+            )
+            .get_matches();
+
+        let config = Config::load(matches.value_of("config").map(Path::new))?;
+        let config = build_site_config(&matches, &config)?;
+
+        Ok(ArgParser { config })
+    }
+
+    /// Take the site config of the arguments
+    pub fn into_config(self) -> SiteConfig {
+        self.config
+    }
+}
+
+/// Put the CLI args over the options of the config file
+fn build_site_config(matches: &ArgMatches, config: &Config) -> Result<SiteConfig, PaperError> {
+    let mut builder = SiteConfigBuilder::new().dist_dir(get_output_dir(matches, config));
+
+    for path in get_input_paths(matches, config) {
+        builder = builder.input_path(path);
+    }
+
+    for url in get_stylesheets(matches, config) {
+        builder = builder.stylesheet(url);
+    }
+
+    if let Some(path) = matches
+        .value_of("template")
+        .map(PathBuf::from)
+        .or_else(|| config.template().cloned())
+    {
+        builder = builder.template(path);
+    }
+
+    if let Some(path) = matches
+        .value_of("theme")
+        .map(PathBuf::from)
+        .or_else(|| config.theme().cloned())
+    {
+        builder = builder.theme(path);
+    }
+
+    if let Some(url) = config.base_url() {
+        builder = builder.base_url(url);
+    }
+
+    if let Some(title) = config.title() {
+        builder = builder.site_title(title);
+    }
+
+    for pattern in config.ignore() {
+        builder = builder.ignore(pattern.as_str());
+    }
+
+    for (dir, front_matter) in config.defaults() {
+        builder = builder.page_defaults(dir, front_matter.clone());
+    }
+
+    let markdown = config.markdown();
+
+    builder
+        .markdown(MarkdownOptions {
+            safe: is_on(matches, "safe", "no-safe", markdown.safe()),
+            tables: is_on(matches, "tables", "no-tables", markdown.tables()),
+            strikethrough: is_on(
+                matches,
+                "strikethrough",
+                "no-strikethrough",
+                markdown.strikethrough(),
+            ),
+            tasklists: is_on(matches, "tasklists", "no-tasklists", markdown.tasklists()),
+            autolinks: is_on(matches, "autolinks", "no-autolinks", markdown.autolinks()),
+            footnotes: is_on(matches, "footnotes", "no-footnotes", markdown.footnotes()),
+            heading_ids: true,
+            heading_anchors: is_on(matches, "anchors", "no-anchors", markdown.anchors()),
+        })
+        .toc(is_on(matches, "toc", "no-toc", markdown.toc()))
+        .fail_fast(matches.is_present("fail-fast"))
+        .build()
+}
+
+/// A flag called `on` that turns an option on, and one called `off` that turns it off.
+/// The last one given overrides the other
+fn switch(
+    on: &'static str,
+    off: &'static str,
+    on_help: &'static str,
+    off_help: &'static str,
+) -> [Arg<'static>; 2] {
+    [
+        Arg::new(on).long(on).help(on_help).overrides_with(off),
+        Arg::new(off).long(off).help(off_help).overrides_with(on),
+    ]
+}
+
+/// Whether the option of the `on` and `off` flags is on: from the flag given,
+/// or else from the config
+fn is_on(matches: &ArgMatches, on: &str, off: &str, config: bool) -> bool {
+    if matches.is_present(on) {
+        true
+    } else if matches.is_present(off) {
+        false
+    } else {
+        config
+    }
+}
+
+/// Get the output dir from CLI arg, or else from the config
+fn get_output_dir(matches: &ArgMatches, config: &Config) -> PathBuf {
+    let mut output_dir = PathBuf::from("./dist");
+    if let Some(path) = matches.value_of("output") {
+        output_dir = PathBuf::from(path);
+    } else if let Some(path) = config.output() {
+        output_dir = path.clone();
+    }
+
+    output_dir
+}
+
+/// Get the input file(s) from CLI arg, or else from the config
+fn get_input_paths(matches: &ArgMatches, config: &Config) -> Vec<PathBuf> {
+    let mut input_paths = config.input().to_vec();
+
+    if let Some(i) = matches.values_of("input") {
+        input_paths = i.map(PathBuf::from).collect();
+    }
+
+    input_paths
+}
+
+/// Get the stylesheets' URLs from CLI arg, or else from the config
+fn get_stylesheets(matches: &ArgMatches, config: &Config) -> Vec<String> {
+    let mut urls = config.stylesheets().to_vec();
+
+    if let Some(values) = matches.values_of("stylesheet") {
+        urls = values.map(str::to_string).collect();
+    }
+
+    urls
+}
@@ -0,0 +1,150 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::generator::Page;
+use crate::error::{Operation, PaperError};
+use crate::file_parser::template_file::template_files;
+use crate::site_config::SiteConfig;
+
+/// The file in the dist dir that keeps the cache of the last build
+pub const CACHE_FILE: &str = ".paper-cache";
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/**
+What a build generated, for the next build to skip the pages whose
+source did not change. The pages are only reused when the render options,
+the templates and the stylesheets are the same as in the last build
+*/
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BuildCache {
+    /// The hash of the version of paper and the render options of the config
+    config: String,
+    /// The hash of the layouts, the partials, the template and the stylesheet files
+    templates: String,
+    /// The pages of the source files, by source path
+    pages: BTreeMap<String, CachedPage>,
+}
+
+/// The page generated from a source file
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct CachedPage {
+    /// The hash of the source file
+    source: String,
+    /// The page, or none for a draft
+    page: Option<Page>,
+}
+
+impl BuildCache {
+    /// An empty cache for a build of `config`
+    pub fn new(config: &SiteConfig) -> BuildCache {
+        let mut templates = String::new();
+        for path in template_files(config) {
+            let content = fs::read(&path).unwrap_or_default();
+            templates += &format!("{} {}\n", path.display(), hash(&content));
+        }
+
+        BuildCache {
+            config: hash(format!("{} {}", VERSION, config.render_options()).as_bytes()),
+            templates: hash(templates.as_bytes()),
+            pages: BTreeMap::new(),
+        }
+    }
+
+    /// Read the cache of the last build into `dist_dir`.
+    /// A cache that is missing or cannot be read is empty
+    pub fn load(dist_dir: &Path) -> BuildCache {
+        fs::read_to_string(dist_dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache into `dist_dir`
+    pub fn save(&self, dist_dir: &Path) -> Result<(), PaperError> {
+        let path = dist_dir.join(CACHE_FILE);
+        let text = toml::to_string(self).map_err(|error| {
+            PaperError::io(
+                &path,
+                Operation::WriteFile,
+                io::Error::new(io::ErrorKind::InvalidData, error),
+            )
+        })?;
+
+        fs::write(&path, text).map_err(|error| PaperError::io(&path, Operation::WriteFile, error))
+    }
+
+    /// Whether the pages of `other` are rendered the same as the ones of this cache
+    pub fn is_compatible(&self, other: &BuildCache) -> bool {
+        self.config == other.config && self.templates == other.templates
+    }
+
+    /// The page of the source file at `path`, if the source hashes to `source`.
+    /// A page with a path outside of the dist dir is never reused
+    pub(crate) fn page(&self, path: &Path, source: &str) -> Option<&CachedPage> {
+        self.pages
+            .get(path.to_str()?)
+            .filter(|page| page.source == source)
+            .filter(|page| page.page().is_none_or(|page| is_in_dist_dir(page.path())))
+    }
+
+    /// Keep the page of the source file at `path`. A page whose source path
+    /// or page path is not UTF-8 cannot be written to the cache, and is left out,
+    /// so that the next build generates it again
+    pub(crate) fn insert(&mut self, path: &Path, page: CachedPage) {
+        let page_path_is_utf8 = page
+            .page()
+            .is_none_or(|page| page.path().to_str().is_some());
+
+        if let (Some(path), true) = (path.to_str(), page_path_is_utf8) {
+            self.pages.insert(path.to_string(), page);
+        }
+    }
+
+    /// The paths of the pages, relative to the dist dir. Paths that lead
+    /// outside of the dist dir, as in a hand-edited cache, are left out
+    pub fn outputs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.pages
+            .values()
+            .filter_map(|page| page.page.as_ref())
+            .map(Page::path)
+            .filter(|path| is_in_dist_dir(path))
+    }
+}
+
+impl CachedPage {
+    pub(crate) fn new(source: String, page: Option<Page>) -> CachedPage {
+        CachedPage { source, page }
+    }
+
+    pub(crate) fn page(&self) -> Option<&Page> {
+        self.page.as_ref()
+    }
+}
+
+/// Whether a path relative to the dist dir stays in it: it is made of names only,
+/// without `..` and without a root
+pub fn is_in_dist_dir(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// A hash of `bytes` that stays the same across builds and versions of Rust, as hex
+pub fn hash(bytes: &[u8]) -> String {
+    // 64-bit FNV-1a
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    format!("{:016x}", hash)
+}
@@ -0,0 +1,163 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use glob::Pattern;
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::diagnostic::line_and_column;
+use crate::error::{Operation, PaperError};
+use crate::file_parser::front_matter::FrontMatter;
+
+/// The config file at the root of a project, read when no `--config` is given
+pub const CONFIG_FILE: &str = "paper.toml";
+
+/// The build options of a `paper.toml`. Command line flags override them
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    input: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    stylesheets: Vec<String>,
+    template: Option<PathBuf>,
+    theme: Option<PathBuf>,
+    base_url: Option<String>,
+    title: Option<String>,
+    markdown: MarkdownConfig,
+    /// Glob patterns of the input paths to skip
+    #[serde(deserialize_with = "deserialize_patterns")]
+    ignore: Vec<Pattern>,
+    /// The front matter that pages in a dir get by default, by dir
+    defaults: BTreeMap<PathBuf, FrontMatter>,
+}
+
+/// The `[markdown]` table, turning on the same options as the flags of the same names
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownConfig {
+    safe: bool,
+    tables: bool,
+    strikethrough: bool,
+    tasklists: bool,
+    autolinks: bool,
+    footnotes: bool,
+    anchors: bool,
+    toc: bool,
+}
+
+impl Config {
+    /// Read the config file at `path`, or else `paper.toml` if there is one.
+    /// Errors give the file and the line of the invalid key or value
+    pub fn load(path: Option<&Path>) -> Result<Config, PaperError> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(CONFIG_FILE).is_file() => Path::new(CONFIG_FILE),
+            None => return Ok(Config::default()),
+        };
+
+        let text = fs::read_to_string(path)
+            .map_err(|error| PaperError::io(path, Operation::ReadFile, error))?;
+
+        toml::from_str(&text).map_err(|error| {
+            let message = error.message().trim_end();
+            let message = match error.span() {
+                Some(span) => {
+                    let (line, column) = line_and_column(&text, span.start);
+                    format!("{}:{}:{}: {}", path.display(), line, column, message)
+                }
+                None => format!("{}: {}", path.display(), message),
+            };
+
+            PaperError::Config { message }
+        })
+    }
+
+    pub fn input(&self) -> &[PathBuf] {
+        &self.input
+    }
+
+    pub fn output(&self) -> Option<&PathBuf> {
+        self.output.as_ref()
+    }
+
+    pub fn stylesheets(&self) -> &[String] {
+        &self.stylesheets
+    }
+
+    pub fn template(&self) -> Option<&PathBuf> {
+        self.template.as_ref()
+    }
+
+    pub fn theme(&self) -> Option<&PathBuf> {
+        self.theme.as_ref()
+    }
+
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn markdown(&self) -> &MarkdownConfig {
+        &self.markdown
+    }
+
+    pub fn ignore(&self) -> &[Pattern] {
+        &self.ignore
+    }
+
+    pub fn defaults(&self) -> &BTreeMap<PathBuf, FrontMatter> {
+        &self.defaults
+    }
+}
+
+impl MarkdownConfig {
+    pub fn safe(&self) -> bool {
+        self.safe
+    }
+
+    pub fn tables(&self) -> bool {
+        self.tables
+    }
+
+    pub fn strikethrough(&self) -> bool {
+        self.strikethrough
+    }
+
+    pub fn tasklists(&self) -> bool {
+        self.tasklists
+    }
+
+    pub fn autolinks(&self) -> bool {
+        self.autolinks
+    }
+
+    pub fn footnotes(&self) -> bool {
+        self.footnotes
+    }
+
+    pub fn anchors(&self) -> bool {
+        self.anchors
+    }
+
+    pub fn toc(&self) -> bool {
+        self.toc
+    }
+}
+
+fn deserialize_patterns<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Pattern>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| {
+            Pattern::new(pattern).map_err(|error| {
+                D::Error::custom(format!("invalid pattern `{}`: {}", pattern, error))
+            })
+        })
+        .collect()
+}
@@ -1,202 +1,427 @@
 use std::{
+    collections::{HashMap, HashSet},
     ffi::OsStr,
     fs::{self, File, OpenOptions},
     io::Write,
+    mem,
     path::{Path, PathBuf},
-    process,
 };
 
-use crate::file_parser::{source_file::SourceFile, template_file::Template};
+use serde::{Deserialize, Serialize};
 
-use super::arg_parser::ArgParser;
+use super::build_cache::{hash, is_in_dist_dir, BuildCache, CachedPage};
+use crate::diagnostic::Diagnostic;
+use crate::error::{Operation, PaperError};
+use crate::file_parser::{escape::escape_html, source_file::SourceFile, template_file::Template};
+use crate::site_config::{SiteConfig, ASSETS_DIR};
 
-/// The core system for managing static site generation
+/**
+The core system for managing static site generation
+
+## Examples
+
+```no_run
+use paper::{Generator, SiteConfig};
+
+let config = SiteConfig::builder().input_path("page.md").build().unwrap();
+
+let mut generator = Generator::with_config(config);
+let result = generator.run();
+
+for diagnostic in generator.diagnostics() {
+    eprintln!("{}", diagnostic);
+}
+result.unwrap();
+```
+*/
 pub struct Generator {
-    args: ArgParser,
+    config: SiteConfig,
+    /// The pages generated so far, to be linked from the index
+    pages: Vec<Page>,
+    /// The source file of each page generated so far, by page path
+    sources: HashMap<PathBuf, PathBuf>,
+    /// The problems found so far, in the order they are found
+    diagnostics: Vec<Diagnostic>,
+    /// The errors of the paths that failed so far
+    errors: Vec<PaperError>,
+    /// The pages of the last build into the dist dir
+    last_build: BuildCache,
+    /// The pages of this build so far
+    cache: BuildCache,
+}
+
+/// A generated page, as listed in the index
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Page {
+    /// The path of the page, relative to the dist dir
+    path: PathBuf,
+    title: String,
+    date: Option<String>,
+    description: Option<String>,
+}
+
+impl Page {
+    /// The path of the page, relative to the dist dir
+    pub(crate) fn path(&self) -> &PathBuf {
+        &self.path
+    }
 }
 
 impl Generator {
-    /// Create a new generator
-    pub fn new() -> Generator {
+    /// Create a generator for the site of `config`
+    pub fn with_config(config: SiteConfig) -> Generator {
         Generator {
-            args: ArgParser::new(),
+            config,
+            pages: vec![],
+            sources: HashMap::new(),
+            diagnostics: vec![],
+            errors: vec![],
+            last_build: BuildCache::default(),
+            cache: BuildCache::default(),
         }
     }
 
-    /// Start generating .html files
-    pub fn run(&self) {
-        self.create_dist_dir();
-        self.generate_dist();
-        self.generate_dist_index_file();
-    }
-
-    /// Create the dist dir for .html files
-    fn create_dist_dir(&self) {
-        if self.args.dist_dir().is_dir() {
-            fs::remove_dir_all(self.args.dist_dir()).unwrap_or_else(|error| {
-                println!("Fail to remove dist dir: {}", error);
-                process::exit(1);
+    /// Start generating .html files. A page that fails is reported in the
+    /// diagnostics, and the build goes on with the other pages, unless
+    /// the config is fail-fast. Fails with `PaperError::Pages` if some pages failed.
+    ///
+    /// The pages of the last build are kept when their source did not change,
+    /// and removed when their source is gone
+    pub fn run(&mut self) -> Result<(), PaperError> {
+        self.create_dist_dir()?;
+        self.copy_theme_assets()?;
+        self.generate_dist()?;
+        self.remove_stale_pages()?;
+        self.generate_dist_index_file()?;
+        self.cache.save(self.config.dist_dir())?;
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PaperError::Pages {
+                errors: mem::take(&mut self.errors),
             })
         }
+    }
+
+    /// The errors and warnings of the pages, in the order they were found
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Create the dist dir for .html files, and read the cache of the last build in it
+    fn create_dist_dir(&mut self) -> Result<(), PaperError> {
+        let dist_dir = self.config.dist_dir();
+
+        fs::create_dir_all(dist_dir)
+            .map_err(|error| PaperError::io(dist_dir, Operation::CreateDir, error))?;
 
-        fs::create_dir_all(&self.args.dist_dir()).unwrap_or_else(|error| {
-            println!("Failed to create dist: {}", error);
-            process::exit(1);
-        });
+        self.last_build = BuildCache::load(dist_dir);
+        self.cache = BuildCache::new(&self.config);
+
+        Ok(())
     }
 
     /// Generate dist files from input files
-    fn generate_dist(&self) {
-        for input_path in self.args.input_paths() {
-            self.generate_dist_from_path(&input_path);
+    fn generate_dist(&mut self) -> Result<(), PaperError> {
+        for input_path in self.config.input_paths().to_vec() {
+            self.generate_dist_from_path(&input_path)?;
         }
+
+        Ok(())
     }
 
     /// Recursively generate dist files from a path
-    fn generate_dist_from_path(&self, path: &PathBuf) {
-        if !path.exists() {
-            println!("Path '{}' does not exist", path.display());
-            process::exit(0);
+    fn generate_dist_from_path(&mut self, path: &PathBuf) -> Result<(), PaperError> {
+        if self.config.is_ignored(path) {
+            return Ok(());
         }
 
-        if path.is_dir() {
-            self.generate_dist_from_dir(&path);
-            return;
-        }
+        let result = if !path.exists() {
+            Err(PaperError::InvalidInput {
+                path: path.clone(),
+                message: "it does not exist".to_string(),
+            })
+        } else if path.is_dir() {
+            self.generate_dist_from_dir(path)
+        } else if path.is_file() {
+            self.generate_dist_from_file(path)
+        } else {
+            Err(PaperError::InvalidInput {
+                path: path.clone(),
+                message: "it is not regconized as file or dir. Try removing trailing slash"
+                    .to_string(),
+            })
+        };
 
-        if path.is_file() {
-            self.generate_dist_from_file(&path);
-            return;
-        }
+        self.keep_going(result)
+    }
 
-        println!("Path is not regconized as file or dir. Try removing trailing slash");
-        process::exit(0);
+    /// Report the error of a path and go on with the build, unless it is fail-fast
+    fn keep_going(&mut self, result: Result<(), PaperError>) -> Result<(), PaperError> {
+        match result {
+            Err(error) if !self.config.fail_fast() => {
+                self.diagnostics.push(Diagnostic::from_error(&error));
+                self.errors.push(error);
+                Ok(())
+            }
+            result => result,
+        }
     }
 
     /// Recursively gEnerate dist file from a dir path
-    fn generate_dist_from_dir(&self, dir_path: &PathBuf) {
+    fn generate_dist_from_dir(&mut self, dir_path: &PathBuf) -> Result<(), PaperError> {
         if !dir_path.is_dir() {
-            return;
+            return Ok(());
         }
 
-        fs::create_dir_all(self.args.dist_dir().join(dir_path)).unwrap_or_else(|error| {
-            println!("Fail to generate dir from '{}'", error);
-            process::exit(1);
-        });
+        let dest_dir = self.config.dist_dir().join(dir_path);
+        fs::create_dir_all(&dest_dir)
+            .map_err(|error| PaperError::io(&dest_dir, Operation::CreateDir, error))?;
 
-        if let Ok(dir) = fs::read_dir(dir_path) {
-            for entry in dir {
-                if let Ok(entry) = entry {
-                    self.generate_dist_from_path(&entry.path());
-                }
-            }
+        let dir = fs::read_dir(dir_path)
+            .map_err(|error| PaperError::io(dir_path, Operation::ReadDir, error))?;
+        for entry in dir.flatten() {
+            self.generate_dist_from_path(&entry.path())?;
         }
+
+        Ok(())
     }
 
-    /// Generate dist from a file path
-    fn generate_dist_from_file(&self, file_path: &PathBuf) {
-        if !file_path.is_file() {
-            return;
+    /// Generate dist from a file path. Files that are not `.md` or `.txt` are skipped
+    fn generate_dist_from_file(&mut self, file_path: &PathBuf) -> Result<(), PaperError> {
+        if !file_path.is_file() || !is_page_source(file_path) {
+            return Ok(());
         }
 
-        let file = SourceFile::new(&file_path).unwrap_or_else(|err| {
-            println!("Problem parsing '{}': {}", file_path.display(), err);
-            process::exit(1);
-        });
+        let content = fs::read_to_string(file_path)
+            .map_err(|error| PaperError::io(file_path, Operation::ReadFile, error))?;
+        let source = hash(content.as_bytes());
+        if self.reuse_page(file_path, &source) {
+            return Ok(());
+        }
+
+        let mut file = SourceFile::from_content(file_path, &content)?;
+
+        for defaults in self.config.page_defaults(file_path) {
+            file.apply_defaults(defaults);
+        }
+
+        let file_path_prefix = file_path.parent().ok_or_else(|| PaperError::InvalidInput {
+            path: file_path.clone(),
+            message: "fail to get path prefix".to_string(),
+        })?;
 
-        let file_path_prefix = file_path.parent().unwrap_or_else(|| {
-            println!("Fail to get path prefix at '{}'", file_path.display());
-            process::exit(1);
-        });
+        let dest_path_prefix = Path::new(self.config.dist_dir()).join(file_path_prefix);
 
-        let dest_path_prefix = Path::new(&self.args.dist_dir()).join(file_path_prefix);
+        fs::create_dir_all(&dest_path_prefix)
+            .map_err(|error| PaperError::io(&dest_path_prefix, Operation::CreateDir, error))?;
 
-        fs::create_dir_all(&dest_path_prefix).unwrap_or_else(|error| {
-            println!(
-                "Fail to create dir(s) for '{}': {}",
-                file_path_prefix.display(),
-                error
-            );
-            process::exit(1);
-        });
+        let front_matter = file.front_matter();
+        if front_matter.draft() {
+            self.cache.insert(file_path, CachedPage::new(source, None));
+            return Ok(());
+        }
 
-        let dest_path = dest_path_prefix.join(format!("{}.html", file.file_stem()));
+        let file_name = front_matter.slug().unwrap_or_else(|| file.file_stem());
+        let dest_path = dest_path_prefix.join(format!("{}.html", file_name));
 
-        let mut template = Template::new();
-	template.parse(&file, &self.args);
+        let template = Template::load(front_matter.layout(), &self.config)
+            .and_then(|mut template| {
+                template.parse(&file, &self.config)?;
+                Ok(template)
+            })
+            .map_err(|error| PaperError::Page {
+                path: file_path.clone(),
+                source: Box::new(error),
+            })?;
+
+        if let Some(source) = self.sources.insert(dest_path.clone(), file_path.clone()) {
+            self.diagnostics.push(Diagnostic::warning(
+                file_path,
+                format!(
+                    "'{}' is generated from '{}' too, and is replaced",
+                    dest_path.display(),
+                    source.display()
+                ),
+            ));
+        }
 
         File::create(&dest_path)
             .and_then(|mut file| file.write_all(template.content().as_bytes()))
-            .unwrap_or_else(|error| {
-                println!(
-                    "Problem generating file '{}': {}",
-                    file_path.display(),
-                    error
-                );
-                process::exit(1);
+            .map_err(|error| PaperError::io(&dest_path, Operation::WriteFile, error))?;
+
+        // pages outside of the dist dir are not listed, nor cached
+        let path = dest_path
+            .strip_prefix(self.config.dist_dir())
+            .ok()
+            .filter(|path| is_in_dist_dir(path));
+        if let Some(path) = path {
+            let page = Page {
+                path: path.to_path_buf(),
+                title: match template.title() {
+                    "" => file.file_stem().to_string(),
+                    title => title.to_string(),
+                },
+                date: front_matter.date().map(str::to_string),
+                description: front_matter.description().map(str::to_string),
+            };
+
+            self.cache
+                .insert(file_path, CachedPage::new(source, Some(page.clone())));
+            self.pages.push(page);
+        }
+
+        Ok(())
+    }
+
+    /// Keep the page of the last build for the source file at `file_path`, if the source
+    /// hashes to `source` and the pages are rendered the same. Returns whether it is kept
+    fn reuse_page(&mut self, file_path: &Path, source: &str) -> bool {
+        if !self.cache.is_compatible(&self.last_build) {
+            return false;
+        }
+
+        let cached = match self.last_build.page(file_path, source) {
+            Some(cached) => cached.clone(),
+            None => return false,
+        };
+
+        if let Some(page) = cached.page() {
+            let dest_path = self.config.dist_dir().join(&page.path);
+            if !dest_path.is_file() {
+                return false;
+            }
+
+            self.sources.insert(dest_path, file_path.to_path_buf());
+            self.pages.push(page.clone());
+        }
+
+        self.cache.insert(file_path, cached);
+        true
+    }
+
+    /// Remove the pages of the last build that this build did not generate,
+    /// like the ones of deleted source files
+    fn remove_stale_pages(&self) -> Result<(), PaperError> {
+        let outputs: HashSet<&PathBuf> = self.cache.outputs().collect();
+
+        for output in self.last_build.outputs() {
+            let path = self.config.dist_dir().join(output);
+            if !outputs.contains(output) && path.is_file() {
+                fs::remove_file(&path)
+                    .map_err(|error| PaperError::io(&path, Operation::RemoveFile, error))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy the assets of the theme into the dist dir. A file of the project's
+    /// asset dir with the same path is copied instead
+    fn copy_theme_assets(&self) -> Result<(), PaperError> {
+        let theme = match self.config.theme() {
+            Some(theme) => theme,
+            None => return Ok(()),
+        };
+
+        if !theme.is_dir() {
+            return Err(PaperError::Config {
+                message: format!("Theme '{}' is not a dir", theme.display()),
             });
+        }
+
+        let theme_assets = theme.join(ASSETS_DIR);
+        if theme_assets.is_dir() {
+            copy_assets(
+                &theme_assets,
+                Path::new(ASSETS_DIR),
+                &self.config.dist_dir().join(ASSETS_DIR),
+            )?;
+        }
+
+        Ok(())
     }
 
     /// Create the index.html file
-    fn generate_dist_index_file(&self) {
+    fn generate_dist_index_file(&self) -> Result<(), PaperError> {
+        let index_path = self.config.dist_dir().join(PathBuf::from("index.html"));
         let mut file = OpenOptions::new()
             .write(true)
             .read(true)
             .create(true)
-            .open(self.args.dist_dir().join(PathBuf::from("index.html")))
-            .unwrap_or_else(|error| {
-                println!("Fail to create dist index.html: {}", error);
-                process::exit(1);
-            });
+            .truncate(true)
+            .open(&index_path)
+            .map_err(|error| PaperError::io(&index_path, Operation::WriteFile, error))?;
+
+        for page in self.pages.iter() {
+            let mut href = page.path.display().to_string();
+            if let Some(base_url) = self.config.base_url() {
+                href = format!("{}/{}", base_url, href);
+            }
+
+            let mut description = String::new();
+            if let Some(text) = &page.description {
+                description = format!(" title=\"{}\"", escape_html(text));
+            }
+
+            let mut date = String::new();
+            if let Some(text) = &page.date {
+                date = format!(" <time>{}</time>", escape_html(text));
+            }
 
-        for path in &self.read_paths(self.args.dist_dir()) {
             file.write_all(
                 format!(
-                    "<a style='display:block' href='{}'>{}</a>",
-                    path.display(),
-                    path.file_stem().unwrap().to_str().unwrap()
+                    "<a style=\"display:block\" href=\"{}\"{}>{}{}</a>",
+                    escape_html(&href),
+                    description,
+                    escape_html(&page.title),
+                    date
                 )
                 .as_bytes(),
             )
-            .unwrap_or_else(|error| {
-                println!("Fail to write path '{}': {}", path.display(), error);
-                process::exit(1);
-            });
+            .map_err(|error| PaperError::io(&index_path, Operation::WriteFile, error))?;
         }
+
+        Ok(())
     }
+}
 
-    /// Add relative links to index.html
-    fn read_paths(&self, path: &PathBuf) -> Vec<PathBuf> {
-        let mut paths: Vec<PathBuf> = Vec::new();
-
-        if let Ok(dir) = fs::read_dir(path) {
-            for entry in dir {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-
-                    if path.is_dir() {
-                        paths.append(&mut self.read_paths(&path));
-                    }
-
-                    if path.is_file() && !path.ends_with("index.html") {
-                        let ext = path.extension().unwrap_or(OsStr::new(""));
-
-                        if ext.eq("html") {
-                            let path = path
-                                .strip_prefix(self.args.dist_dir())
-                                .unwrap_or_else(|error| {
-                                    println!("Fail to strip prefix: {}", error);
-                                    process::exit(9);
-                                })
-                                .to_path_buf();
-
-                            paths.push(path);
-                        }
-                    }
-                }
-            }
+/// Whether the file at `path` is rendered into a page, as a `.md` or `.txt` file
+fn is_page_source(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(OsStr::to_str),
+        Some("md") | Some("txt")
+    )
+}
+
+/// Recursively copy the files of `from` into `to`, taking a file from `project_dir`
+/// instead when it has one with the same path
+fn copy_assets(from: &Path, project_dir: &Path, to: &Path) -> Result<(), PaperError> {
+    fs::create_dir_all(to).map_err(|error| PaperError::io(to, Operation::CreateDir, error))?;
+
+    let dir =
+        fs::read_dir(from).map_err(|error| PaperError::io(from, Operation::ReadDir, error))?;
+
+    for entry in dir.flatten() {
+        let name = entry.file_name();
+        let path = entry.path();
+
+        if path.is_dir() {
+            copy_assets(&path, &project_dir.join(&name), &to.join(&name))?;
+            continue;
         }
 
-        paths
+        let project_file = project_dir.join(&name);
+        let source = if project_file.is_file() {
+            project_file
+        } else {
+            path
+        };
+
+        fs::copy(&source, to.join(&name))
+            .map_err(|error| PaperError::io(&source, Operation::CopyFile, error))?;
     }
+
+    Ok(())
 }
@@ -1,2 +1,3 @@
-pub mod arg_parser;
+pub mod build_cache;
+pub mod config;
 pub mod generator;
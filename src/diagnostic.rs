@@ -0,0 +1,143 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::error::PaperError;
+
+/// How bad a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The page is generated, but maybe not as expected
+    Warning,
+    /// The page is not generated
+    Error,
+}
+
+/// A problem with a page, found while building the site. The build goes on
+/// past them unless it is fail-fast
+#[derive(Debug)]
+pub struct Diagnostic {
+    path: Option<PathBuf>,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+    severity: Severity,
+}
+
+impl Diagnostic {
+    /// A warning about the file at `path`
+    pub fn warning(path: &Path, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            path: Some(path.to_path_buf()),
+            line: None,
+            column: None,
+            message: message.into(),
+            severity: Severity::Warning,
+        }
+    }
+
+    /// An error diagnostic, with the file and the position of `error` when known
+    pub fn from_error(error: &PaperError) -> Diagnostic {
+        let mut diagnostic = Diagnostic {
+            path: None,
+            line: None,
+            column: None,
+            message: error.to_string(),
+            severity: Severity::Error,
+        };
+
+        match error {
+            PaperError::Io {
+                path,
+                operation,
+                source,
+            } => {
+                diagnostic.path = Some(path.clone());
+                diagnostic.message = format!("Fail to {}: {}", operation, source);
+            }
+            PaperError::InvalidInput { path, message } => {
+                diagnostic.path = Some(path.clone());
+                diagnostic.message = message.clone();
+            }
+            PaperError::FrontMatter {
+                path,
+                position,
+                message,
+            } => {
+                diagnostic.path = Some(path.clone());
+                diagnostic.line = position.map(|(line, _)| line);
+                diagnostic.column = position.map(|(_, column)| column);
+                diagnostic.message = message.clone();
+            }
+            PaperError::Page { path, source } => {
+                diagnostic.path = Some(path.clone());
+                diagnostic.message = source.to_string();
+            }
+            PaperError::Config { .. } | PaperError::Template { .. } | PaperError::Pages { .. } => {}
+        }
+
+        diagnostic
+    }
+
+    /// The file of the problem, if it is about one
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The 1-based line of the problem in the file, when known
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The 1-based column of the problem in the file, when known
+    pub fn column(&self) -> Option<usize> {
+        self.column
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    /// Print the diagnostic as `path:line:column: severity: message`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}", path.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+            write!(f, ": ")?;
+        }
+
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The 1-based line and column of the byte at `offset`
+pub(crate) fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
@@ -0,0 +1,167 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/**
+An error of a site build
+
+The binary exits with the code of `exit_code()`. A build that went on past failed
+pages exits with the highest code of them:
+
+| Code | Meaning                                                                        |
+| ---- | ------------------------------------------------------------------------------ |
+| 0    | The site is generated                                                          |
+| 1    | Bad input: a missing input path, an invalid config, front matter or template   |
+| 2    | Invalid command line arguments                                                 |
+| 3    | I/O failure: a file or dir cannot be read, written, created or removed         |
+*/
+#[derive(Debug)]
+pub enum PaperError {
+    /// A file system operation on `path` failed
+    Io {
+        path: PathBuf,
+        operation: Operation,
+        source: io::Error,
+    },
+    /// An input path that does not exist, or is neither a file nor a dir
+    InvalidInput { path: PathBuf, message: String },
+    /// An invalid config. The message gives the file and the line when known
+    Config { message: String },
+    /// A source file with invalid front matter, with the line and the column
+    /// of the error in the file when known
+    FrontMatter {
+        path: PathBuf,
+        position: Option<(usize, usize)>,
+        message: String,
+    },
+    /// A template that cannot be found, parsed or rendered.
+    /// The message gives the template and the line when known
+    Template { message: String },
+    /// An error while generating the page of the source file at `path`
+    Page {
+        path: PathBuf,
+        source: Box<PaperError>,
+    },
+    /// The pages that failed in a build that went on past them
+    Pages { errors: Vec<PaperError> },
+}
+
+/// A file system operation that can fail
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    ReadFile,
+    WriteFile,
+    CopyFile,
+    RemoveFile,
+    CreateDir,
+    ReadDir,
+    RemoveDir,
+}
+
+/// The exit code of a build with bad input
+pub const EXIT_BAD_INPUT: i32 = 1;
+/// The exit code of a build with invalid command line arguments
+pub const EXIT_USAGE: i32 = 2;
+/// The exit code of a build that fails to read or write files
+pub const EXIT_IO: i32 = 3;
+
+impl PaperError {
+    /// Wrap an `io::Error` of `operation` on `path`
+    pub fn io(path: impl Into<PathBuf>, operation: Operation, source: io::Error) -> PaperError {
+        PaperError::Io {
+            path: path.into(),
+            operation,
+            source,
+        }
+    }
+
+    /// An error of a template, with the message of the template engine
+    pub fn template(message: impl Into<String>) -> PaperError {
+        PaperError::Template {
+            message: message.into(),
+        }
+    }
+
+    /// The code the binary exits with for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PaperError::Io { .. } => EXIT_IO,
+            PaperError::Page { source, .. } => source.exit_code(),
+            PaperError::Pages { errors } => errors
+                .iter()
+                .map(PaperError::exit_code)
+                .max()
+                .unwrap_or(EXIT_BAD_INPUT),
+            PaperError::InvalidInput { .. }
+            | PaperError::Config { .. }
+            | PaperError::FrontMatter { .. }
+            | PaperError::Template { .. } => EXIT_BAD_INPUT,
+        }
+    }
+}
+
+impl fmt::Display for PaperError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaperError::Io {
+                path,
+                operation,
+                source,
+            } => write!(f, "Fail to {} '{}': {}", operation, path.display(), source),
+            PaperError::InvalidInput { path, message } => {
+                write!(f, "Invalid input path '{}': {}", path.display(), message)
+            }
+            PaperError::Config { message } => write!(f, "Problem parsing config: {}", message),
+            PaperError::FrontMatter {
+                path,
+                position: Some((line, column)),
+                message,
+            } => write!(
+                f,
+                "Problem parsing '{}' at line {}, column {}: {}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+            PaperError::FrontMatter { path, message, .. } => {
+                write!(f, "Problem parsing '{}': {}", path.display(), message)
+            }
+            PaperError::Template { message } => write!(f, "{}", message),
+            PaperError::Page { path, source } => {
+                write!(
+                    f,
+                    "Problem generating file '{}': {}",
+                    path.display(),
+                    source
+                )
+            }
+            PaperError::Pages { errors } => write!(f, "Fail to generate {} page(s)", errors.len()),
+        }
+    }
+}
+
+impl Error for PaperError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PaperError::Io { source, .. } => Some(source),
+            PaperError::Page { source, .. } => Some(source.as_ref()),
+            PaperError::Pages { errors } => errors.first().map(|error| error as &dyn Error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self {
+            Operation::ReadFile => "read file",
+            Operation::WriteFile => "write file",
+            Operation::CopyFile => "copy file",
+            Operation::RemoveFile => "remove file",
+            Operation::CreateDir => "create dir",
+            Operation::ReadDir => "read dir",
+            Operation::RemoveDir => "remove dir",
+        };
+
+        write!(f, "{}", operation)
+    }
+}
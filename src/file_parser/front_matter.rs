@@ -0,0 +1,241 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+use serde::Deserialize;
+
+use crate::diagnostic::line_and_column;
+use crate::error::PaperError;
+
+/// The metadata of a page, from the YAML (`---`) or TOML (`+++`) front matter
+/// at the top of its source
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FrontMatter {
+    title: Option<String>,
+    date: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    draft: Option<bool>,
+    layout: Option<String>,
+    slug: Option<String>,
+    /// Any other key, for the template to use
+    #[serde(flatten)]
+    extra: BTreeMap<String, Value>,
+}
+
+/// The value of a key that is not part of the known metadata
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Table(BTreeMap<String, Value>),
+}
+
+impl FrontMatter {
+    /// Split the front matter off the top of `content`, and return it with the rest
+    /// of the content. Without front matter, the metadata is empty and the content
+    /// is left as it is. Errors give the line and the column in the file at `path`
+    pub fn parse<'a>(path: &Path, content: &'a str) -> Result<(FrontMatter, &'a str), PaperError> {
+        let (format, text, rest) = match split_front_matter(content) {
+            Some(parts) => parts,
+            None => return Ok((FrontMatter::default(), content)),
+        };
+
+        if text.trim().is_empty() {
+            return Ok((FrontMatter::default(), rest));
+        }
+
+        // the opening fence is put back as a blank line,
+        // so that errors point at the line of the source file
+        let text = format!("\n{}", text);
+
+        let mut front_matter: FrontMatter = match format {
+            Format::Yaml => serde_yaml::from_str(&text).map_err(|error| {
+                let position = error
+                    .location()
+                    .map(|location| (location.line(), location.column()));
+                // the position is given apart, and is left out of the message
+                let mut message = error.to_string();
+                if let Some((line, column)) = position {
+                    let suffix = format!(" at line {} column {}", line, column);
+                    message = message.trim_end_matches(&suffix).to_string();
+                }
+
+                PaperError::FrontMatter {
+                    path: path.to_path_buf(),
+                    position,
+                    message: format!("Invalid YAML front matter: {}", message),
+                }
+            })?,
+            Format::Toml => toml::from_str::<toml::Table>(&text)
+                .and_then(|table| stringify_datetimes(toml::Value::Table(table)).try_into())
+                .map_err(|error| PaperError::FrontMatter {
+                    path: path.to_path_buf(),
+                    position: error.span().map(|span| line_and_column(&text, span.start)),
+                    message: format!(
+                        "Invalid TOML front matter: {}",
+                        error.message().trim_end().replace('\n', ", ")
+                    ),
+                })?,
+        };
+
+        front_matter.flatten_extra();
+
+        if let Some(slug) = front_matter.slug() {
+            check_slug(slug).map_err(|message| PaperError::FrontMatter {
+                path: path.to_path_buf(),
+                position: key_line(&text, "slug").map(|line| (line, 1)),
+                message,
+            })?;
+        }
+
+        Ok((front_matter, rest))
+    }
+
+    /// Fill in the keys that are not set with the ones of `defaults`
+    pub fn apply_defaults(&mut self, defaults: &FrontMatter) {
+        let mut defaults = defaults.clone();
+        defaults.flatten_extra();
+
+        self.title = self.title.take().or(defaults.title);
+        self.date = self.date.take().or(defaults.date);
+        self.description = self.description.take().or(defaults.description);
+        self.tags = self.tags.take().or(defaults.tags);
+        self.draft = self.draft.or(defaults.draft);
+        self.layout = self.layout.take().or(defaults.layout);
+        self.slug = self.slug.take().or(defaults.slug);
+
+        for (key, value) in defaults.extra {
+            self.extra.entry(key).or_insert(value);
+        }
+    }
+
+    /// Keys under an `extra` table are extra keys too
+    fn flatten_extra(&mut self) {
+        if let Some(Value::Table(table)) = self.extra.remove("extra") {
+            self.extra.extend(table);
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn date(&self) -> Option<&str> {
+        self.date.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_deref().unwrap_or_default()
+    }
+
+    /// A draft page is not generated
+    pub fn draft(&self) -> bool {
+        self.draft.unwrap_or_default()
+    }
+
+    /// The name of the layout to render the page with
+    pub fn layout(&self) -> Option<&str> {
+        self.layout.as_deref()
+    }
+
+    /// The file name of the generated page, without its extension
+    pub fn slug(&self) -> Option<&str> {
+        self.slug.as_deref()
+    }
+
+    pub fn extra(&self) -> &BTreeMap<String, Value> {
+        &self.extra
+    }
+}
+
+/// Check that a slug is a plain file name, so that the page is generated
+/// in the dir of its source, and not outside of the dist dir
+pub fn check_slug(slug: &str) -> Result<(), String> {
+    let mut components = Path::new(slug).components();
+    let is_file_name = !slug.contains(['/', '\\'])
+        && matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+
+    if is_file_name {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid slug `{}`: it must be a file name, without `/`, `\\` or `..`",
+            slug
+        ))
+    }
+}
+
+/// The 1-based line of the top-level `key` in the front matter `text`
+fn key_line(text: &str, key: &str) -> Option<usize> {
+    text.lines()
+        .position(|line| {
+            line.strip_prefix(key)
+                .is_some_and(|rest| rest.trim_start().starts_with([':', '=']))
+        })
+        .map(|index| index + 1)
+}
+
+enum Format {
+    Yaml,
+    Toml,
+}
+
+/// Find a front matter fenced by `---` or `+++` lines at the top of `content`.
+/// Returns its format, its text and the content after the closing fence
+fn split_front_matter(content: &str) -> Option<(Format, &str, &str)> {
+    let first_line_end = content.find('\n').unwrap_or(content.len());
+    let (format, fence) = match content[..first_line_end].trim_end() {
+        "---" => (Format::Yaml, "---"),
+        "+++" => (Format::Toml, "+++"),
+        _ => return None,
+    };
+
+    let text_start = (first_line_end + 1).min(content.len());
+    let mut line_start = text_start;
+
+    for line in content[text_start..].split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let rest_start = line_start + line.len();
+            return Some((
+                format,
+                &content[text_start..line_start],
+                &content[rest_start..],
+            ));
+        }
+        line_start += line.len();
+    }
+
+    None
+}
+
+/// TOML dates are kept as the text they are written as
+fn stringify_datetimes(value: toml::Value) -> toml::Value {
+    match value {
+        toml::Value::Datetime(datetime) => toml::Value::String(datetime.to_string()),
+        toml::Value::Array(values) => {
+            toml::Value::Array(values.into_iter().map(stringify_datetimes).collect())
+        }
+        toml::Value::Table(table) => toml::Value::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, stringify_datetimes(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
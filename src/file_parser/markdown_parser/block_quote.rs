@@ -0,0 +1,66 @@
+use super::{line::Line, BlockElement, Continuation, DocumentContext};
+
+/// A block quote, holding any block element
+pub struct BlockQuote {
+    children: Vec<BlockElement>,
+}
+
+impl BlockQuote {
+    /// Parse the `>` marker of a block quote at the cursor of `line`,
+    /// moving the cursor to the start of the quote's content
+    pub fn parse_start(line: &mut Line) -> Option<BlockQuote> {
+        if !BlockQuote::skip_marker(line) {
+            return None;
+        }
+
+        Some(BlockQuote { children: vec![] })
+    }
+
+    /// A block quote continues on lines starting with `>`. Other lines can
+    /// only continue its paragraph lazily
+    pub fn continuation(&self, line: &mut Line) -> Continuation {
+        if BlockQuote::skip_marker(line) {
+            Continuation::Matched
+        } else {
+            Continuation::Unmatched
+        }
+    }
+
+    /// Move the cursor past a `>` marker and the optional space following it
+    fn skip_marker(line: &mut Line) -> bool {
+        if line.is_indented() || !line.rest_after_indent().starts_with('>') {
+            return false;
+        }
+
+        line.skip_indent();
+        line.advance(1, false);
+
+        if matches!(line.peek(), Some(' ') | Some('\t')) {
+            line.advance(1, true);
+        }
+
+        true
+    }
+
+    pub fn children(&self) -> &[BlockElement] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut [BlockElement] {
+        &mut self.children
+    }
+
+    pub fn add_child(&mut self, child: BlockElement) {
+        self.children.push(child);
+    }
+
+    pub fn print(&self, context: &DocumentContext) -> String {
+        let children = self
+            .children
+            .iter()
+            .map(|child| child.print(context))
+            .fold(String::new(), |acc, s| acc + &s);
+
+        format!("<blockquote>\n{}</blockquote>\n", children)
+    }
+}
@@ -0,0 +1,138 @@
+use super::{entity::unescape_string, is_blank, line::Line, Continuation};
+use crate::file_parser::escape::escape_html;
+
+/// A fenced or an indented code block, keeping its content verbatim
+pub struct CodeBlock {
+    /// The opening fence, or `None` for an indented code block
+    fence: Option<Fence>,
+    info: String,
+    content: String,
+}
+
+/// The ```` ``` ```` or `~~~` line that opens a fenced code block
+struct Fence {
+    character: char,
+    length: usize,
+    indentation: usize,
+}
+
+impl CodeBlock {
+    /// Create an empty indented code block
+    pub fn indented() -> CodeBlock {
+        CodeBlock {
+            fence: None,
+            info: String::new(),
+            content: String::new(),
+        }
+    }
+
+    /// Create a fenced code block if `text` is an opening code fence,
+    /// indented by `indentation` columns
+    pub fn from_opening_fence(text: &str, indentation: usize) -> Option<CodeBlock> {
+        let character = text.chars().next().filter(|&c| c == '`' || c == '~')?;
+        let length = text.chars().take_while(|&c| c == character).count();
+
+        if length < 3 {
+            return None;
+        }
+
+        let info = text[length..].trim();
+        if character == '`' && info.contains('`') {
+            return None;
+        }
+
+        Some(CodeBlock {
+            fence: Some(Fence {
+                character,
+                length,
+                indentation,
+            }),
+            info: unescape_string(info),
+            content: String::new(),
+        })
+    }
+
+    /// A fenced code block takes every line until its closing fence, and an
+    /// indented code block takes the blank lines and the lines indented with 4 columns
+    pub fn continuation(&self, line: &mut Line) -> Continuation {
+        match &self.fence {
+            Some(fence) => {
+                if !line.is_indented() && self.is_closing_fence(line.rest_after_indent()) {
+                    return Continuation::Finished;
+                }
+
+                let mut columns = fence.indentation;
+                while columns > 0 && matches!(line.peek(), Some(' ') | Some('\t')) {
+                    line.advance(1, true);
+                    columns -= 1;
+                }
+
+                Continuation::Matched
+            }
+            None if line.is_indented() => {
+                line.advance(4, true);
+                Continuation::Matched
+            }
+            None if line.is_blank() => {
+                line.skip_indent();
+                Continuation::Matched
+            }
+            None => Continuation::Unmatched,
+        }
+    }
+
+    /// Whether `text`, without its indentation, closes a fenced code block
+    fn is_closing_fence(&self, text: &str) -> bool {
+        let fence = match &self.fence {
+            Some(fence) => fence,
+            None => return false,
+        };
+
+        let length = text.chars().take_while(|&c| c == fence.character).count();
+
+        length >= fence.length && is_blank(&text[length..])
+    }
+
+    pub fn add_line(&mut self, text: &str) {
+        self.content += text;
+        self.content.push('\n');
+    }
+
+    /// Drop the blank lines at the end of an indented code block,
+    /// and return how many were dropped
+    pub fn close(&mut self) -> usize {
+        let mut dropped_lines = 0;
+
+        if self.fence.is_some() {
+            return dropped_lines;
+        }
+
+        while self.content.ends_with('\n') {
+            let last_line_start = self.content[..self.content.len() - 1]
+                .rfind('\n')
+                .map_or(0, |i| i + 1);
+
+            if !is_blank(&self.content[last_line_start..self.content.len() - 1]) {
+                break;
+            }
+
+            self.content.truncate(last_line_start);
+            dropped_lines += 1;
+        }
+
+        dropped_lines
+    }
+
+    pub fn print(&self) -> String {
+        let class = match self.info.split_whitespace().next() {
+            Some(language) => format!(" class=\"language-{}\"", escape_html(language)),
+            None => String::new(),
+        };
+
+        format!(
+            "<pre><code{}>{}</code></pre>\n",
+            class,
+            escape_html(&self.content)
+        )
+    }
+}
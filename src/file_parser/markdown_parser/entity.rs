@@ -0,0 +1,88 @@
+use entities::ENTITIES;
+
+/// Parse an entity reference like `&amp;`, `&#35;` or `&#x22;` at the start of `text`.
+/// Returns the characters it stands for and its length
+pub fn parse_entity_reference(text: &str) -> Option<(String, usize)> {
+    let end = text.find(';')?;
+    let name = text.get(1..end)?;
+
+    if let Some(number) = name.strip_prefix('#') {
+        let (digits, radix, max_length) = match number.strip_prefix(['x', 'X']) {
+            Some(hex_digits) => (hex_digits, 16, 6),
+            None => (number, 10, 7),
+        };
+
+        if digits.is_empty()
+            || digits.len() > max_length
+            || !digits.chars().all(|c| c.is_digit(radix))
+        {
+            return None;
+        }
+
+        // the null character and invalid code points are replaced
+        let character = u32::from_str_radix(digits, radix)
+            .ok()
+            .filter(|&code| code != 0)
+            .and_then(char::from_u32)
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+
+        return Some((character.to_string(), end + 1));
+    }
+
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    let reference = &text[..end + 1];
+    ENTITIES
+        .iter()
+        .find(|entity| entity.entity == reference)
+        .map(|entity| (entity.characters.to_string(), end + 1))
+}
+
+/// Replace the backslash escapes and the entity references of `text` with
+/// the characters they stand for, as done for link destinations, titles
+/// and the info string of code blocks
+pub fn unescape_string(text: &str) -> String {
+    unescape(text, true)
+}
+
+/// Replace the entity references of `text` with the characters they stand for,
+/// as done for the attribute values of raw HTML
+pub fn decode_entities(text: &str) -> String {
+    unescape(text, false)
+}
+
+fn unescape(text: &str, backslash_escapes: bool) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut position = 0;
+
+    while let Some(c) = text[position..].chars().next() {
+        let rest = &text[position..];
+
+        match c {
+            '\\' if backslash_escapes
+                && rest[1..].starts_with(|next: char| next.is_ascii_punctuation()) =>
+            {
+                unescaped.push_str(&rest[1..2]);
+                position += 2;
+            }
+            '&' => match parse_entity_reference(rest) {
+                Some((characters, length)) => {
+                    unescaped.push_str(&characters);
+                    position += length;
+                }
+                None => {
+                    unescaped.push('&');
+                    position += 1;
+                }
+            },
+            _ => {
+                unescaped.push(c);
+                position += c.len_utf8();
+            }
+        }
+    }
+
+    unescaped
+}
@@ -0,0 +1,182 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use super::{
+    inline_parser::normalize_label, line::Line, BlockElement, Continuation, DocumentContext,
+};
+
+/// A `[^label]: text` footnote definition, holding any block element.
+/// It is taken out of the document once closed, and printed at its end
+pub struct FootnoteDefinition {
+    label: String,
+    children: Vec<BlockElement>,
+}
+
+/// The footnotes of a document. Definitions are collected while parsing, and
+/// references are numbered as they are printed, so in first-reference order
+#[derive(Default)]
+pub struct Footnotes {
+    definitions: HashMap<String, FootnoteDefinition>,
+    /// The label and the number of references of each referenced footnote,
+    /// ordered by footnote number
+    references: RefCell<Vec<(String, usize)>>,
+}
+
+impl FootnoteDefinition {
+    /// Parse the `[^label]:` start of a footnote definition at the cursor of `line`,
+    /// moving the cursor to the start of its content
+    pub fn parse_start(line: &mut Line) -> Option<FootnoteDefinition> {
+        if line.is_indented() {
+            return None;
+        }
+
+        let rest = line.rest_after_indent();
+        let (label, length) = parse_footnote_label(rest)?;
+        if !rest[length..].starts_with(':') {
+            return None;
+        }
+
+        let definition = FootnoteDefinition {
+            label: normalize_label(label),
+            children: vec![],
+        };
+
+        line.skip_indent();
+        line.advance(length + 1, false);
+        line.skip_indent();
+
+        Some(definition)
+    }
+
+    /// A definition continues on blank lines and on lines indented by 4 columns.
+    /// Other lines can only continue its paragraph lazily
+    pub fn continuation(&self, line: &mut Line) -> Continuation {
+        if line.is_blank() {
+            line.skip_indent();
+            Continuation::Matched
+        } else if line.indent() >= 4 {
+            line.advance(4, true);
+            Continuation::Matched
+        } else {
+            Continuation::Unmatched
+        }
+    }
+
+    pub fn add_child(&mut self, child: BlockElement) {
+        self.children.push(child);
+    }
+}
+
+impl Footnotes {
+    /// Add a closed definition. The first definition of a label wins
+    pub fn define(&mut self, definition: FootnoteDefinition) {
+        self.definitions
+            .entry(definition.label.clone())
+            .or_insert(definition);
+    }
+
+    /// Record a reference to the footnote `label`, if it is defined.
+    /// Returns the number of the footnote and the index of the reference,
+    /// counting from 1
+    pub fn reference(&self, label: &str) -> Option<(usize, usize)> {
+        let label = normalize_label(label);
+        if !self.definitions.contains_key(&label) {
+            return None;
+        }
+
+        let mut references = self.references.borrow_mut();
+        match references.iter().position(|(l, _)| *l == label) {
+            Some(index) => {
+                references[index].1 += 1;
+                Some((index + 1, references[index].1))
+            }
+            None => {
+                references.push((label, 1));
+                Some((references.len(), 1))
+            }
+        }
+    }
+
+    /// Forget the references, before printing the document again
+    pub fn clear_references(&self) {
+        self.references.borrow_mut().clear();
+    }
+
+    /// Print the referenced footnotes as a numbered list with back-references.
+    /// Call it after the rest of the document has been printed
+    pub fn print(&self, context: &DocumentContext) -> String {
+        let mut contents = vec![];
+
+        // printing a footnote may reference footnotes that were not referenced yet
+        while contents.len() < self.references.borrow().len() {
+            let label = self.references.borrow()[contents.len()].0.clone();
+            let content = self.definitions[&label]
+                .children
+                .iter()
+                .map(|child| child.print(context))
+                .fold(String::new(), |acc, s| acc + &s);
+            contents.push(content);
+        }
+
+        if contents.is_empty() {
+            return String::new();
+        }
+
+        let mut result = String::from("<section class=\"footnotes\">\n<ol>\n");
+
+        for (i, content) in contents.iter().enumerate() {
+            let number = i + 1;
+            let backreferences = (1..=self.references.borrow()[i].1)
+                .map(|index| print_backreference(number, index))
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            // the back-references go at the end of the last paragraph, if there is one
+            let content = match content.strip_suffix("</p>\n") {
+                Some(content) => format!("{} {}</p>\n", content, backreferences),
+                None => format!("{}<p>{}</p>\n", content, backreferences),
+            };
+
+            result += &format!("<li id=\"fn-{}\">\n{}</li>\n", number, content);
+        }
+
+        result + "</ol>\n</section>\n"
+    }
+}
+
+/// Parse the `[^label]` at the start of `text`. Returns the label and the length
+/// of the whole reference. A label cannot hold whitespace or brackets
+pub fn parse_footnote_label(text: &str) -> Option<(&str, usize)> {
+    let rest = text.strip_prefix("[^")?;
+    let end = rest.find(']')?;
+    let label = &rest[..end];
+
+    if label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '[') {
+        return None;
+    }
+
+    Some((label, end + 3))
+}
+
+/// The id of the `index`-th reference to the footnote `number`
+pub fn reference_id(number: usize, index: usize) -> String {
+    if index == 1 {
+        format!("fnref-{}", number)
+    } else {
+        format!("fnref-{}-{}", number, index)
+    }
+}
+
+/// A link back to the `index`-th reference to the footnote `number`
+fn print_backreference(number: usize, index: usize) -> String {
+    let label = if index == 1 {
+        String::new()
+    } else {
+        format!("<sup>{}</sup>", index)
+    };
+
+    format!(
+        "<a href=\"#{}\" class=\"footnote-backref\">↩{}</a>",
+        reference_id(number, index),
+        label
+    )
+}
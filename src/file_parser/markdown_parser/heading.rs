@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use super::{
+    inline_parser::{parse_inlines, print_inlines, print_plain_text},
+    DocumentContext,
+};
+use crate::file_parser::escape::escape_html;
+
+/// An ATX or setext heading, holding the raw text of its inline content
+pub struct Heading {
+    level: usize,
+    text: String,
+    id: Option<String>,
+}
+
+impl Heading {
+    pub fn new(level: usize, text: String) -> Heading {
+        Heading {
+            level,
+            text,
+            id: None,
+        }
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// The text of the heading without any markup
+    pub fn plain_text(&self, context: &DocumentContext) -> String {
+        print_plain_text(&parse_inlines(&self.text, context))
+    }
+
+    /// Give the heading the id set by a trailing `{#custom-id}`, or else a GitHub-style
+    /// slug of its text. An id already in `used_ids` gets a `-1`, `-2`... suffix.
+    /// `used_ids` keeps the last suffix given to each id
+    pub fn assign_id(&mut self, used_ids: &mut HashMap<String, usize>, context: &DocumentContext) {
+        let base = match self.take_custom_id() {
+            Some(id) => id,
+            None => slugify(&self.plain_text(context)),
+        };
+
+        let mut id = base.clone();
+        if let Some(&last_suffix) = used_ids.get(&base) {
+            let mut suffix = last_suffix;
+            loop {
+                suffix += 1;
+                id = format!("{}-{}", base, suffix);
+                if !used_ids.contains_key(&id) {
+                    break;
+                }
+            }
+            used_ids.insert(base, suffix);
+        }
+
+        used_ids.entry(id.clone()).or_insert(0);
+        self.id = Some(id);
+    }
+
+    /// Remove a `{#custom-id}` from the end of the text, and return the id
+    fn take_custom_id(&mut self) -> Option<String> {
+        let text = self.text.trim_end().strip_suffix('}')?;
+        let start = text.rfind("{#")?;
+        let id = &text[start + 2..];
+
+        if id.is_empty()
+            || id.contains(|c: char| c.is_whitespace() || c == '{' || c == '}')
+            || !(start == 0 || text[..start].ends_with([' ', '\t']))
+        {
+            return None;
+        }
+
+        let id = id.to_string();
+        self.text.truncate(start);
+        self.text.truncate(self.text.trim_end().len());
+
+        Some(id)
+    }
+
+    pub fn print(&self, context: &DocumentContext) -> String {
+        let inner = print_inlines(&parse_inlines(&self.text, context));
+
+        let id = match &self.id {
+            Some(id) => escape_html(id),
+            None => return format!("<h{level}>{}</h{level}>\n", inner, level = self.level),
+        };
+
+        let anchor = if context.options.heading_anchors {
+            format!(" <a class=\"anchor\" href=\"#{}\">¶</a>", id)
+        } else {
+            String::new()
+        };
+
+        format!(
+            "<h{level} id=\"{id}\">{inner}{anchor}</h{level}>\n",
+            level = self.level,
+            id = id,
+            inner = inner,
+            anchor = anchor
+        )
+    }
+}
+
+/// Print a `<nav>` with nested lists of links to `headings`, following their levels.
+/// Headings without an id are left out
+pub fn print_table_of_contents(headings: &[&Heading], context: &DocumentContext) -> String {
+    let mut result = String::new();
+    // the levels of the open lists, from the outermost one
+    let mut levels: Vec<usize> = vec![];
+
+    for heading in headings.iter() {
+        let id = match &heading.id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        match levels.last() {
+            None => {
+                result += "<ul>\n";
+                levels.push(heading.level);
+            }
+            Some(&level) if heading.level > level => {
+                result += "\n<ul>\n";
+                levels.push(heading.level);
+            }
+            Some(_) => {
+                result += "</li>\n";
+                while levels.len() > 1 && heading.level <= levels[levels.len() - 2] {
+                    levels.pop();
+                    result += "</ul>\n</li>\n";
+                }
+            }
+        }
+
+        result += &format!(
+            "<li><a href=\"#{}\">{}</a>",
+            escape_html(id),
+            escape_html(&heading.plain_text(context))
+        );
+    }
+
+    if levels.is_empty() {
+        return String::new();
+    }
+
+    for _ in levels.iter() {
+        result += "</li>\n</ul>\n";
+    }
+
+    format!("<nav class=\"toc\">\n{}</nav>\n", result)
+}
+
+/// Lowercase `text`, drop the punctuation other than `-` and `_`,
+/// and turn spaces into `-`, like GitHub does for heading anchors
+fn slugify(text: &str) -> String {
+    let slug: String = text
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            '-' | '_' => Some(c),
+            c if c.is_whitespace() => Some('-'),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect();
+
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    }
+}
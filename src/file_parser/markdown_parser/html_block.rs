@@ -0,0 +1,197 @@
+use super::{is_blank, line::Line, raw_html::parse_tag, Continuation};
+
+/// Tags whose content is kept raw, up to their closing tag (kind 1)
+const RAW_TEXT_TAGS: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+/// Tags that start a block that runs until a blank line (kind 6)
+const BLOCK_TAGS: [&str; 62] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
+
+/// A block of raw HTML, kept verbatim. Its kind is one of the 7 start
+/// conditions of the CommonMark spec, and decides how the block ends
+/// (https://spec.commonmark.org/0.31.2/#html-blocks)
+pub struct HtmlBlock {
+    kind: u8,
+    content: String,
+    finished: bool,
+}
+
+impl HtmlBlock {
+    /// Create an HTML block if `text`, without its indentation, starts one.
+    /// Only the kinds 1 to 6 can interrupt a paragraph
+    pub fn from_start(text: &str, interrupts_paragraph: bool) -> Option<HtmlBlock> {
+        let kind = html_block_kind(text)?;
+
+        if kind == 7 && interrupts_paragraph {
+            return None;
+        }
+
+        Some(HtmlBlock {
+            kind,
+            content: String::new(),
+            finished: false,
+        })
+    }
+
+    /// The kinds 6 and 7 end at a blank line, the others end on
+    /// the line holding their end marker
+    pub fn continuation(&self, line: &Line) -> Continuation {
+        if self.kind >= 6 && line.is_blank() {
+            Continuation::Unmatched
+        } else {
+            Continuation::Matched
+        }
+    }
+
+    /// Add a line, and remember if it holds the end marker of the block
+    pub fn add_line(&mut self, text: &str) {
+        self.content += text;
+        self.content.push('\n');
+
+        let text = text.to_ascii_lowercase();
+        self.finished = match self.kind {
+            1 => RAW_TEXT_TAGS
+                .iter()
+                .any(|tag| text.contains(&format!("</{}>", tag))),
+            2 => text.contains("-->"),
+            3 => text.contains("?>"),
+            4 => text.contains('>'),
+            5 => text.contains("]]>"),
+            _ => false,
+        };
+    }
+
+    /// Whether the last line added ended the block
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The content of the block, ending with a line ending
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// The kind of HTML block started by `text`, from 1 to 7
+fn html_block_kind(text: &str) -> Option<u8> {
+    if !text.starts_with('<') {
+        return None;
+    }
+
+    let lowercase = text.to_ascii_lowercase();
+
+    let tag_name_end = lowercase[1..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .map_or(lowercase.len(), |i| i + 1);
+    let tag_name = &lowercase[1..tag_name_end];
+    let after_tag_name = &lowercase[tag_name_end..];
+
+    if RAW_TEXT_TAGS.contains(&tag_name)
+        && (after_tag_name.is_empty() || after_tag_name.starts_with([' ', '\t', '>']))
+    {
+        return Some(1);
+    }
+
+    if lowercase.starts_with("<!--") {
+        return Some(2);
+    }
+
+    if lowercase.starts_with("<?") {
+        return Some(3);
+    }
+
+    if lowercase.starts_with("<!") && lowercase[2..].starts_with(|c: char| c.is_ascii_alphabetic())
+    {
+        return Some(4);
+    }
+
+    if text.starts_with("<![CDATA[") {
+        return Some(5);
+    }
+
+    let block_tag = lowercase.strip_prefix("</").unwrap_or(&lowercase[1..]);
+    let block_tag_name_end = block_tag
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(block_tag.len());
+    let after_block_tag_name = &block_tag[block_tag_name_end..];
+
+    if BLOCK_TAGS.contains(&&block_tag[..block_tag_name_end])
+        && (after_block_tag_name.is_empty()
+            || after_block_tag_name.starts_with([' ', '\t', '>'])
+            || after_block_tag_name.starts_with("/>"))
+    {
+        return Some(6);
+    }
+
+    match parse_tag(text) {
+        Some((name, length))
+            if !RAW_TEXT_TAGS.contains(&name.to_ascii_lowercase().as_str())
+                && is_blank(&text[length..]) =>
+        {
+            Some(7)
+        }
+        _ => None,
+    }
+}
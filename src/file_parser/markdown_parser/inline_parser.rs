@@ -0,0 +1,1133 @@
+use std::collections::HashMap;
+
+use super::{
+    entity::{parse_entity_reference, unescape_string},
+    footnote::{parse_footnote_label, reference_id},
+    normalize_url,
+    raw_html::{is_safe_url, parse_raw_html, sanitize_html},
+    DocumentContext,
+};
+use crate::file_parser::escape::escape_html;
+
+/// An inline element inside a heading or a paragraph
+pub enum InlineElement {
+    Text(String),
+    SoftBreak,
+    HardBreak,
+    Code(String),
+    Emphasis(Vec<InlineElement>),
+    Strong(Vec<InlineElement>),
+    Strikethrough(Vec<InlineElement>),
+    Link {
+        destination: String,
+        title: Option<String>,
+        children: Vec<InlineElement>,
+    },
+    Image {
+        source: String,
+        title: Option<String>,
+        alt: Vec<InlineElement>,
+    },
+    Autolink {
+        destination: String,
+        text: String,
+    },
+    /// Raw HTML, printed as it is
+    Html(String),
+    /// The `index`-th reference to the footnote `number`
+    FootnoteReference {
+        number: usize,
+        index: usize,
+    },
+}
+
+impl InlineElement {
+    pub fn print(&self) -> String {
+        match self {
+            InlineElement::Text(text) => escape_html(text),
+            InlineElement::SoftBreak => "\n".to_string(),
+            InlineElement::HardBreak => "<br />\n".to_string(),
+            InlineElement::Code(code) => format!("<code>{}</code>", escape_html(code)),
+            InlineElement::Emphasis(children) => format!("<em>{}</em>", print_inlines(children)),
+            InlineElement::Strong(children) => {
+                format!("<strong>{}</strong>", print_inlines(children))
+            }
+            InlineElement::Strikethrough(children) => {
+                format!("<del>{}</del>", print_inlines(children))
+            }
+            InlineElement::Link {
+                destination,
+                title,
+                children,
+            } => format!(
+                "<a href=\"{}\"{}>{}</a>",
+                escape_html(&normalize_url(destination)),
+                print_title(title),
+                print_inlines(children)
+            ),
+            InlineElement::Image { source, title, alt } => format!(
+                "<img src=\"{}\" alt=\"{}\"{} />",
+                escape_html(&normalize_url(source)),
+                escape_html(&print_plain_text(alt)),
+                print_title(title)
+            ),
+            InlineElement::Autolink { destination, text } => format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(&normalize_url(destination)),
+                escape_html(text)
+            ),
+            InlineElement::Html(html) => html.to_string(),
+            InlineElement::FootnoteReference { number, index } => format!(
+                "<sup class=\"footnote-ref\"><a href=\"#fn-{number}\" id=\"{id}\">{number}</a></sup>",
+                number = number,
+                id = reference_id(*number, *index)
+            ),
+        }
+    }
+
+    /// Print the element without any markup, as used for the alt text of images
+    fn print_plain_text(&self) -> String {
+        match self {
+            InlineElement::Text(text) | InlineElement::Code(text) => text.to_string(),
+            InlineElement::SoftBreak | InlineElement::HardBreak => "\n".to_string(),
+            InlineElement::Emphasis(children)
+            | InlineElement::Strong(children)
+            | InlineElement::Strikethrough(children)
+            | InlineElement::Link { children, .. }
+            | InlineElement::Image { alt: children, .. } => print_plain_text(children),
+            InlineElement::Autolink { text, .. } => text.to_string(),
+            // footnote numbers are not part of the text of a title, a TOC entry or an id
+            InlineElement::Html(_) | InlineElement::FootnoteReference { .. } => String::new(),
+        }
+    }
+}
+
+/// Print a list of inline elements one after another
+pub fn print_inlines(elements: &[InlineElement]) -> String {
+    elements
+        .iter()
+        .map(|e| e.print())
+        .fold(String::new(), |acc, s| acc + &s)
+}
+
+/// The destination and title of a `[label]: destination "title"` definition
+pub struct LinkReference {
+    destination: String,
+    title: Option<String>,
+}
+
+/// Link reference definitions of a document, keyed by their normalized label
+pub type LinkReferences = HashMap<String, LinkReference>;
+
+/// Print a list of inline elements without any markup
+pub fn print_plain_text(elements: &[InlineElement]) -> String {
+    elements
+        .iter()
+        .map(|e| e.print_plain_text())
+        .fold(String::new(), |acc, s| acc + &s)
+}
+
+/// Print the title attribute of a link or an image, if it has one
+fn print_title(title: &Option<String>) -> String {
+    match title {
+        Some(title) => format!(" title=\"{}\"", escape_html(title)),
+        None => String::new(),
+    }
+}
+
+/// Parse the raw text of a heading or a paragraph into inline elements
+pub fn parse_inlines(text: &str, context: &DocumentContext) -> Vec<InlineElement> {
+    let mut parser = InlineParser::new(text, context);
+    parser.parse();
+
+    let elements = parser.into_elements();
+    if context.options.autolinks {
+        link_bare_urls(elements)
+    } else {
+        elements
+    }
+}
+
+/// Turn the bare URLs in text elements into autolinks, outside of links and code
+fn link_bare_urls(elements: Vec<InlineElement>) -> Vec<InlineElement> {
+    let mut linked = vec![];
+
+    for element in elements {
+        match element {
+            InlineElement::Text(text) => linked.extend(split_bare_urls(&text)),
+            InlineElement::Emphasis(children) => {
+                linked.push(InlineElement::Emphasis(link_bare_urls(children)))
+            }
+            InlineElement::Strong(children) => {
+                linked.push(InlineElement::Strong(link_bare_urls(children)))
+            }
+            InlineElement::Strikethrough(children) => {
+                linked.push(InlineElement::Strikethrough(link_bare_urls(children)))
+            }
+            element => linked.push(element),
+        }
+    }
+
+    linked
+}
+
+/// Split `text` into text and autolinks. A bare URL starts at the start of the text,
+/// after whitespace, or after `*`, `_`, `~` or `(`
+fn split_bare_urls(text: &str) -> Vec<InlineElement> {
+    let mut elements = vec![];
+    let mut text_start = 0;
+    let mut position = 0;
+
+    while let Some(c) = text[position..].chars().next() {
+        let at_boundary = text[..position]
+            .chars()
+            .next_back()
+            .is_none_or(|previous| previous.is_whitespace() || "*_~(".contains(previous));
+
+        match parse_bare_url(&text[position..]).filter(|_| at_boundary) {
+            Some(url) => {
+                if text_start < position {
+                    elements.push(InlineElement::Text(text[text_start..position].to_string()));
+                }
+
+                let destination = if url.starts_with("www.") {
+                    format!("http://{}", url)
+                } else {
+                    url.to_string()
+                };
+                elements.push(InlineElement::Autolink {
+                    destination,
+                    text: url.to_string(),
+                });
+
+                position += url.len();
+                text_start = position;
+            }
+            None => position += c.len_utf8(),
+        }
+    }
+
+    if text_start < text.len() {
+        elements.push(InlineElement::Text(text[text_start..].to_string()));
+    }
+
+    elements
+}
+
+/// Parse a `www.`, `http://` or `https://` URL at the start of `text`, as defined by
+/// the extended autolinks of GitHub-flavored Markdown. Trailing punctuation,
+/// unbalanced closing parentheses and entity-like `&name;` are left out of it
+fn parse_bare_url(text: &str) -> Option<&str> {
+    let domain_start = if text.starts_with("www.") {
+        0
+    } else if let Some(rest) = text.strip_prefix("http://") {
+        text.len() - rest.len()
+    } else if let Some(rest) = text.strip_prefix("https://") {
+        text.len() - rest.len()
+    } else {
+        return None;
+    };
+
+    let mut url = &text[..text
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(text.len())];
+
+    loop {
+        let unbalanced_parenthesis =
+            url.ends_with(')') && url.matches(')').count() > url.matches('(').count();
+
+        if url.ends_with(['?', '!', '.', ',', ':', '*', '_', '~']) || unbalanced_parenthesis {
+            url = &url[..url.len() - 1];
+        } else if let Some(entity_start) = url
+            .strip_suffix(';')
+            .and_then(|url| url.rfind('&'))
+            .filter(|&start| {
+                let name = &url[start + 1..url.len() - 1];
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric())
+            })
+        {
+            url = &url[..entity_start];
+        } else {
+            break;
+        }
+    }
+
+    let domain = &url[domain_start..];
+    let domain = &domain[..domain
+        .find(|c: char| !c.is_alphanumeric() && !matches!(c, '.' | '_' | '-'))
+        .unwrap_or(domain.len())];
+    let segments: Vec<&str> = domain.split('.').collect();
+
+    // at least one period, and no underscores in the last two segments
+    let valid_domain = segments.len() >= 2
+        && segments.iter().all(|segment| !segment.is_empty())
+        && segments
+            .iter()
+            .rev()
+            .take(2)
+            .all(|segment| !segment.contains('_'));
+
+    if valid_domain {
+        Some(url)
+    } else {
+        None
+    }
+}
+
+/// An inline element with a stable id, so delimiters can find it
+/// after the elements around it have been moved
+struct Node {
+    id: usize,
+    element: InlineElement,
+}
+
+/// A run of `*` or `_` that may open or close emphasis, or a run of `~`
+/// that may open or close strikethrough
+struct Delimiter {
+    node_id: usize,
+    character: char,
+    length: usize,
+    original_length: usize,
+    can_open: bool,
+    can_close: bool,
+}
+
+/// A `[` or `![` that may open a link or an image
+struct Bracket {
+    node_id: usize,
+    is_image: bool,
+    /// Links cannot contain other links, so brackets before a link get deactivated
+    active: bool,
+    /// The size of the delimiter stack when the bracket was pushed
+    previous_delimiter: usize,
+    /// Where the text after the bracket starts, to be used as a reference label
+    text_start: usize,
+}
+
+/// Parses inline elements with the delimiter stack algorithm described in
+/// the CommonMark spec (https://spec.commonmark.org/0.31.2/#phase-2-inline-structure)
+struct InlineParser<'a> {
+    text: &'a str,
+    context: &'a DocumentContext,
+    position: usize,
+    nodes: Vec<Node>,
+    delimiters: Vec<Delimiter>,
+    brackets: Vec<Bracket>,
+    next_node_id: usize,
+}
+
+impl<'a> InlineParser<'a> {
+    fn new(text: &'a str, context: &'a DocumentContext) -> InlineParser<'a> {
+        InlineParser {
+            text,
+            context,
+            position: 0,
+            nodes: vec![],
+            delimiters: vec![],
+            brackets: vec![],
+            next_node_id: 0,
+        }
+    }
+
+    fn parse(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                '`' => self.parse_backticks(),
+                '*' | '_' => self.parse_delimiter_run(c),
+                '~' if self.context.options.strikethrough => self.parse_delimiter_run(c),
+                '[' if self.context.options.footnotes => self.parse_footnote_reference(),
+                '[' => self.parse_open_bracket(),
+                '!' if self.text[self.position..].starts_with("![") => self.parse_open_bracket(),
+                ']' => self.parse_close_bracket(),
+                '<' => self.parse_autolink(),
+                '\n' => self.parse_newline(),
+                '\\' => self.parse_backslash(),
+                '&' => self.parse_entity_reference(),
+                _ => self.parse_text(),
+            }
+        }
+
+        self.process_emphasis(0);
+    }
+
+    /// Consume the parser, merging adjacent text nodes
+    fn into_elements(self) -> Vec<InlineElement> {
+        let mut elements: Vec<InlineElement> = vec![];
+
+        for node in self.nodes {
+            match (elements.last_mut(), node.element) {
+                (Some(InlineElement::Text(previous)), InlineElement::Text(text)) => {
+                    previous.push_str(&text)
+                }
+                (_, element) => elements.push(element),
+            }
+        }
+
+        elements
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn push_node(&mut self, element: InlineElement) -> usize {
+        let id = self.next_node_id;
+        self.next_node_id += 1;
+        self.nodes.push(Node { id, element });
+        id
+    }
+
+    fn node_index(&self, id: usize) -> usize {
+        self.nodes
+            .iter()
+            .position(|node| node.id == id)
+            .expect("delimiter points to a removed node")
+    }
+
+    /// Consume plain text up to the next character that may start an inline element
+    fn parse_text(&mut self) {
+        let rest = &self.text[self.position..];
+        let end = rest
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| {
+                matches!(
+                    c,
+                    '`' | '*' | '_' | '~' | '[' | ']' | '!' | '<' | '\n' | '\\' | '&'
+                )
+            })
+            .map_or(rest.len(), |(i, _)| i);
+
+        self.position += end;
+        self.push_node(InlineElement::Text(rest[..end].to_string()));
+    }
+
+    /// A line ending becomes a soft break, dropping the spaces around it,
+    /// or a hard break when the line ends with 2 spaces or more
+    fn parse_newline(&mut self) {
+        self.position += 1;
+        let mut is_hard_break = false;
+
+        if let Some(Node {
+            element: InlineElement::Text(text),
+            ..
+        }) = self.nodes.last_mut()
+        {
+            let trimmed_length = text.trim_end_matches(' ').len();
+            is_hard_break = text.len() - trimmed_length >= 2;
+            text.truncate(trimmed_length);
+        }
+
+        if is_hard_break {
+            self.push_line_break(InlineElement::HardBreak);
+        } else {
+            self.push_line_break(InlineElement::SoftBreak);
+        }
+    }
+
+    /// A backslash escapes the punctuation character after it,
+    /// and a backslash at the end of a line is a hard break
+    fn parse_backslash(&mut self) {
+        let next = self.text[self.position + 1..].chars().next();
+
+        if next == Some('\n') {
+            self.position += 2;
+            self.push_line_break(InlineElement::HardBreak);
+        } else if let Some(c) = next.filter(char::is_ascii_punctuation) {
+            self.position += 2;
+            self.push_node(InlineElement::Text(c.to_string()));
+        } else {
+            self.position += 1;
+            self.push_node(InlineElement::Text("\\".to_string()));
+        }
+    }
+
+    /// Decode an entity reference, or treat the `&` as literal text
+    fn parse_entity_reference(&mut self) {
+        match parse_entity_reference(&self.text[self.position..]) {
+            Some((characters, length)) => {
+                self.position += length;
+                self.push_node(InlineElement::Text(characters));
+            }
+            None => {
+                self.position += 1;
+                self.push_node(InlineElement::Text("&".to_string()));
+            }
+        }
+    }
+
+    /// Add a line break, dropping the spaces at the start of the next line
+    fn push_line_break(&mut self, line_break: InlineElement) {
+        self.push_node(line_break);
+
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start_matches(' ').len();
+    }
+
+    /// Parse a code span, or a literal backtick string when it has no closing run
+    fn parse_backticks(&mut self) {
+        let opening_length = count_run(&self.text[self.position..], '`');
+        let content_start = self.position + opening_length;
+        let mut search_start = content_start;
+
+        while let Some(offset) = self.text[search_start..].find('`') {
+            let closing_start = search_start + offset;
+            let closing_length = count_run(&self.text[closing_start..], '`');
+
+            if closing_length == opening_length {
+                let content = normalize_code_span(&self.text[content_start..closing_start]);
+                self.position = closing_start + closing_length;
+                self.push_node(InlineElement::Code(content));
+                return;
+            }
+
+            search_start = closing_start + closing_length;
+        }
+
+        self.position = content_start;
+        self.push_node(InlineElement::Text("`".repeat(opening_length)));
+    }
+
+    /// Parse a `[^label]` reference to a defined footnote,
+    /// or push the `[` as a bracket that may start a link
+    fn parse_footnote_reference(&mut self) {
+        let reference =
+            parse_footnote_label(&self.text[self.position..]).and_then(|(label, length)| {
+                self.context
+                    .footnotes
+                    .reference(label)
+                    .map(|(number, index)| (number, index, length))
+            });
+
+        match reference {
+            Some((number, index, length)) => {
+                self.position += length;
+                self.push_node(InlineElement::FootnoteReference { number, index });
+            }
+            None => self.parse_open_bracket(),
+        }
+    }
+
+    /// Push a `[` or `![` that may start a link or an image
+    fn parse_open_bracket(&mut self) {
+        let is_image = self.text[self.position..].starts_with('!');
+        let length = if is_image { 2 } else { 1 };
+        let node_id = self.push_node(InlineElement::Text(
+            self.text[self.position..self.position + length].to_string(),
+        ));
+        self.position += length;
+
+        self.brackets.push(Bracket {
+            node_id,
+            is_image,
+            active: true,
+            previous_delimiter: self.delimiters.len(),
+            text_start: self.position,
+        });
+    }
+
+    /// Try closing the last opened bracket as a link or an image,
+    /// or treat the `]` as literal text
+    fn parse_close_bracket(&mut self) {
+        self.position += 1;
+
+        let bracket = match self.brackets.pop() {
+            Some(bracket) if bracket.active => bracket,
+            _ => {
+                self.push_node(InlineElement::Text("]".to_string()));
+                return;
+            }
+        };
+
+        let bracket_text = &self.text[bracket.text_start..self.position - 1];
+        let link = parse_inline_link(self.text, self.position)
+            .or_else(|| self.parse_reference_link(bracket_text));
+
+        let (destination, title, end) = match link {
+            Some(link) => link,
+            None => {
+                self.push_node(InlineElement::Text("]".to_string()));
+                return;
+            }
+        };
+        self.position = end;
+        let destination = self.filter_url(destination);
+
+        self.process_emphasis(bracket.previous_delimiter);
+
+        let bracket_position = self.node_index(bracket.node_id);
+        let children = self
+            .nodes
+            .drain(bracket_position + 1..)
+            .map(|node| node.element)
+            .collect();
+
+        self.nodes[bracket_position].element = if bracket.is_image {
+            InlineElement::Image {
+                source: destination,
+                title,
+                alt: children,
+            }
+        } else {
+            // links may not contain other links
+            for bracket in self.brackets.iter_mut().filter(|b| !b.is_image) {
+                bracket.active = false;
+            }
+
+            InlineElement::Link {
+                destination,
+                title,
+                children,
+            }
+        };
+    }
+
+    /// Resolve a full `[text][label]`, collapsed `[label][]` or shortcut `[label]`
+    /// reference, whose text has just been closed by `]`.
+    /// Returns the destination, the title and the position after the reference
+    fn parse_reference_link(&self, bracket_text: &str) -> Option<(String, Option<String>, usize)> {
+        let (label, end) = match parse_link_label(self.text, self.position) {
+            Some((label, end)) if !label.is_empty() => (label, end),
+            Some((_, end)) => (bracket_text, end),
+            None => (bracket_text, self.position),
+        };
+
+        if !is_valid_link_label(label) {
+            return None;
+        }
+
+        self.context
+            .link_references
+            .get(&normalize_label(label))
+            .map(|reference| {
+                (
+                    reference.destination.to_string(),
+                    reference.title.clone(),
+                    end,
+                )
+            })
+    }
+
+    /// In safe mode, a destination with an unsafe scheme like `javascript:` is dropped
+    fn filter_url(&self, url: String) -> String {
+        if self.context.options.safe && !is_safe_url(&url) {
+            String::new()
+        } else {
+            url
+        }
+    }
+
+    /// Parse a URI or an email address between `<` and `>`, or raw HTML,
+    /// or treat the `<` as literal text
+    fn parse_autolink(&mut self) {
+        let rest = &self.text[self.position + 1..];
+        let content = rest
+            .find(|c: char| c == '>' || c == '<' || c.is_ascii_whitespace() || c.is_control())
+            .filter(|&end| rest[end..].starts_with('>'))
+            .map(|end| &rest[..end]);
+
+        match content {
+            Some(uri) if is_absolute_uri(uri) => {
+                self.position += uri.len() + 2;
+                self.push_node(InlineElement::Autolink {
+                    destination: self.filter_url(uri.to_string()),
+                    text: uri.to_string(),
+                });
+            }
+            Some(email) if is_email_address(email) => {
+                self.position += email.len() + 2;
+                self.push_node(InlineElement::Autolink {
+                    destination: format!("mailto:{}", email),
+                    text: email.to_string(),
+                });
+            }
+            _ => self.parse_raw_html(),
+        }
+    }
+
+    /// Parse inline raw HTML, escaped in safe mode, or treat the `<` as literal text
+    fn parse_raw_html(&mut self) {
+        let rest = &self.text[self.position..];
+
+        match parse_raw_html(rest) {
+            Some(length) if self.context.options.safe => {
+                self.position += length;
+                self.push_node(InlineElement::Html(sanitize_html(&rest[..length])));
+            }
+            Some(length) => {
+                self.position += length;
+                self.push_node(InlineElement::Html(rest[..length].to_string()));
+            }
+            None => {
+                self.position += 1;
+                self.push_node(InlineElement::Text("<".to_string()));
+            }
+        }
+    }
+
+    /// Parse a run of `*`, `_` or `~` and push it to the delimiter stack if it is flanking.
+    /// A run of more than 2 `~` is plain text
+    fn parse_delimiter_run(&mut self, character: char) {
+        let start = self.position;
+        let length = count_run(&self.text[start..], character);
+        let end = start + length * character.len_utf8();
+
+        let before = self.text[..start].chars().next_back();
+        let after = self.text[end..].chars().next();
+
+        let before_is_whitespace = before.is_none_or(char::is_whitespace);
+        let after_is_whitespace = after.is_none_or(char::is_whitespace);
+        let before_is_punctuation = before.is_some_and(is_punctuation);
+        let after_is_punctuation = after.is_some_and(is_punctuation);
+
+        let left_flanking = !after_is_whitespace
+            && (!after_is_punctuation || before_is_whitespace || before_is_punctuation);
+        let right_flanking = !before_is_whitespace
+            && (!before_is_punctuation || after_is_whitespace || after_is_punctuation);
+
+        let (can_open, can_close) = match character {
+            '_' => (
+                left_flanking && (!right_flanking || before_is_punctuation),
+                right_flanking && (!left_flanking || after_is_punctuation),
+            ),
+            '~' if length > 2 => (false, false),
+            _ => (left_flanking, right_flanking),
+        };
+
+        self.position = end;
+        let node_id = self.push_node(InlineElement::Text(self.text[start..end].to_string()));
+
+        if can_open || can_close {
+            self.delimiters.push(Delimiter {
+                node_id,
+                character,
+                length,
+                original_length: length,
+                can_open,
+                can_close,
+            });
+        }
+    }
+
+    /// Match the delimiters above `stack_bottom` into emphasis, strong emphasis
+    /// and strikethrough
+    fn process_emphasis(&mut self, stack_bottom: usize) {
+        // the lowest index an opener may have, keyed by the kind of closer looking for it
+        let mut openers_bottom: Vec<((char, usize, bool), usize)> = vec![];
+        let mut closer_index = stack_bottom;
+
+        while closer_index < self.delimiters.len() {
+            if !self.delimiters[closer_index].can_close {
+                closer_index += 1;
+                continue;
+            }
+
+            let closer = &self.delimiters[closer_index];
+            let key = (
+                closer.character,
+                closer.original_length % 3,
+                closer.can_open,
+            );
+            let lowest_opener = openers_bottom
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map_or(stack_bottom, |(_, index)| *index);
+
+            let opener_index = (lowest_opener..closer_index).rev().find(|&i| {
+                let opener = &self.delimiters[i];
+
+                // strikethrough only pairs runs of the same length
+                if closer.character == '~' {
+                    return opener.character == '~'
+                        && opener.can_open
+                        && opener.length == closer.length;
+                }
+
+                let breaks_rule_of_three = (opener.can_close || closer.can_open)
+                    && (opener.original_length + closer.original_length).is_multiple_of(3)
+                    && !(opener.original_length.is_multiple_of(3)
+                        && closer.original_length.is_multiple_of(3));
+
+                opener.character == closer.character && opener.can_open && !breaks_rule_of_three
+            });
+
+            let opener_index = match opener_index {
+                Some(index) => index,
+                None => {
+                    openers_bottom.retain(|(k, _)| *k != key);
+                    openers_bottom.push((key, closer_index));
+
+                    if self.delimiters[closer_index].can_open {
+                        closer_index += 1;
+                    } else {
+                        self.delimiters.remove(closer_index);
+                    }
+                    continue;
+                }
+            };
+
+            let used = if self.delimiters[closer_index].character == '~' {
+                self.delimiters[closer_index].length
+            } else if self.delimiters[opener_index].length >= 2
+                && self.delimiters[closer_index].length >= 2
+            {
+                2
+            } else {
+                1
+            };
+
+            self.shrink_delimiter(opener_index, used);
+            self.shrink_delimiter(closer_index, used);
+
+            let opener_position = self.node_index(self.delimiters[opener_index].node_id);
+            let closer_position = self.node_index(self.delimiters[closer_index].node_id);
+            let children = self
+                .nodes
+                .drain(opener_position + 1..closer_position)
+                .map(|node| node.element)
+                .collect();
+            let element = if self.delimiters[closer_index].character == '~' {
+                InlineElement::Strikethrough(children)
+            } else if used == 2 {
+                InlineElement::Strong(children)
+            } else {
+                InlineElement::Emphasis(children)
+            };
+            let id = self.next_node_id;
+            self.next_node_id += 1;
+            self.nodes.insert(opener_position + 1, Node { id, element });
+
+            // delimiters between the opener and the closer can no longer match
+            self.delimiters.drain(opener_index + 1..closer_index);
+            for (_, index) in openers_bottom.iter_mut() {
+                *index = (*index).min(opener_index + 1);
+            }
+            closer_index = opener_index + 1;
+
+            if self.delimiters[opener_index].length == 0 {
+                self.remove_delimiter_and_node(opener_index);
+                closer_index -= 1;
+                for (_, index) in openers_bottom.iter_mut() {
+                    if *index > opener_index {
+                        *index -= 1;
+                    }
+                }
+            }
+
+            if self.delimiters[closer_index].length == 0 {
+                self.remove_delimiter_and_node(closer_index);
+            }
+        }
+
+        self.delimiters.truncate(stack_bottom);
+    }
+
+    /// Remove `count` characters from a delimiter run and its text node
+    fn shrink_delimiter(&mut self, delimiter_index: usize, count: usize) {
+        let delimiter = &mut self.delimiters[delimiter_index];
+        delimiter.length -= count;
+        let text = delimiter.character.to_string().repeat(delimiter.length);
+
+        let position = self.node_index(self.delimiters[delimiter_index].node_id);
+        self.nodes[position].element = InlineElement::Text(text);
+    }
+
+    fn remove_delimiter_and_node(&mut self, delimiter_index: usize) {
+        let delimiter = self.delimiters.remove(delimiter_index);
+        let position = self.node_index(delimiter.node_id);
+        self.nodes.remove(position);
+    }
+}
+
+/// Parse a link reference definition at the start of `text`, and
+/// return its normalized label, its reference and where the rest of the text starts
+pub fn parse_link_reference_definition(text: &str) -> Option<(String, LinkReference, usize)> {
+    let (label, after_label) = parse_link_label(text, 0)?;
+
+    if !is_valid_link_label(label) || !text[after_label..].starts_with(':') {
+        return None;
+    }
+
+    let destination_start = skip_whitespace(text, after_label + 1);
+    let (destination, after_destination) = parse_link_destination(text, destination_start)?;
+
+    let title_start = skip_whitespace(text, after_destination);
+    if title_start > after_destination {
+        if let Some((title, after_title)) = parse_link_title(text, title_start) {
+            if let Some(end) = end_of_blank_line(text, after_title) {
+                let reference = LinkReference {
+                    destination,
+                    title: Some(title),
+                };
+                return Some((normalize_label(label), reference, end));
+            }
+        }
+    }
+
+    let end = end_of_blank_line(text, after_destination)?;
+    let reference = LinkReference {
+        destination,
+        title: None,
+    };
+    Some((normalize_label(label), reference, end))
+}
+
+/// Parse a `[label]` starting at `start`.
+/// Returns the text between the brackets and the position after the `]`
+fn parse_link_label(text: &str, start: usize) -> Option<(&str, usize)> {
+    let rest = &text[start..];
+    if !rest.starts_with('[') {
+        return None;
+    }
+
+    let mut chars = rest.char_indices().skip(1).peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars
+                .peek()
+                .is_some_and(|(_, next)| next.is_ascii_punctuation()) =>
+            {
+                chars.next();
+            }
+            ']' => return Some((&rest[1..i], start + i + 1)),
+            '[' => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// A label has at most 999 characters, no unescaped brackets,
+/// and at least one character that is not whitespace
+fn is_valid_link_label(label: &str) -> bool {
+    let mut escaped = false;
+
+    for c in label.chars() {
+        match c {
+            '[' | ']' if !escaped => return false,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+
+    label.chars().count() <= 999 && !label.trim().is_empty()
+}
+
+/// Labels match case-insensitively, with consecutive whitespace collapsed
+pub fn normalize_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+        .to_uppercase()
+}
+
+/// If the rest of the line after `start` is blank, return where the next line starts
+fn end_of_blank_line(text: &str, start: usize) -> Option<usize> {
+    let rest = &text[start..];
+    let line_end = rest.find('\n').unwrap_or(rest.len());
+
+    if !rest[..line_end].trim_matches([' ', '\t']).is_empty() {
+        return None;
+    }
+
+    Some((start + line_end + 1).min(text.len()))
+}
+
+/// Parse the `(destination "title")` part of an inline link, starting at `start`.
+/// Returns the destination, the title and the position right after the `)`
+fn parse_inline_link(text: &str, start: usize) -> Option<(String, Option<String>, usize)> {
+    if !text[start..].starts_with('(') {
+        return None;
+    }
+
+    let mut position = skip_whitespace(text, start + 1);
+
+    let destination = match parse_link_destination(text, position) {
+        Some((destination, end)) => {
+            position = end;
+            destination
+        }
+        None => String::new(),
+    };
+
+    let before_title = position;
+    position = skip_whitespace(text, position);
+
+    let mut title = None;
+    if position > before_title {
+        if let Some((parsed_title, end)) = parse_link_title(text, position) {
+            title = Some(parsed_title);
+            position = skip_whitespace(text, end);
+        }
+    }
+
+    if text[position..].starts_with(')') {
+        Some((destination, title, position + 1))
+    } else {
+        None
+    }
+}
+
+/// Parse a link destination, either `<bracketed>` or a run of non-space characters
+/// with balanced parentheses. Returns the destination and the position after it
+fn parse_link_destination(text: &str, start: usize) -> Option<(String, usize)> {
+    let rest = &text[start..];
+    let mut chars = rest.char_indices().peekable();
+
+    if rest.starts_with('<') {
+        chars.next();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' if chars
+                    .peek()
+                    .is_some_and(|(_, next)| next.is_ascii_punctuation()) =>
+                {
+                    chars.next();
+                }
+                '>' => return Some((unescape_string(&rest[1..i]), start + i + 1)),
+                '\n' | '<' => return None,
+                _ => {}
+            }
+        }
+
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut end = rest.len();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars
+                .peek()
+                .is_some_and(|(_, next)| next.is_ascii_punctuation()) =>
+            {
+                chars.next();
+            }
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                end = i;
+                break;
+            }
+            ')' => depth -= 1,
+            c if c == ' ' || c.is_ascii_control() => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    if end == 0 || depth != 0 {
+        return None;
+    }
+
+    Some((unescape_string(&rest[..end]), start + end))
+}
+
+/// Parse a link title wrapped in `"`, `'` or `()`.
+/// Returns the title and the position after it
+fn parse_link_title(text: &str, start: usize) -> Option<(String, usize)> {
+    let rest = &text[start..];
+    let closing = match rest.chars().next()? {
+        '"' => '"',
+        '\'' => '\'',
+        '(' => ')',
+        _ => return None,
+    };
+
+    let mut chars = rest.char_indices().skip(1).peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\\'
+            && chars
+                .peek()
+                .is_some_and(|(_, next)| next.is_ascii_punctuation())
+        {
+            chars.next();
+        } else if c == closing {
+            return Some((unescape_string(&rest[1..i]), start + i + 1));
+        } else if c == '(' && closing == ')' {
+            return None;
+        }
+    }
+
+    None
+}
+
+/// Skip spaces, tabs and line endings starting at `start`
+pub(super) fn skip_whitespace(text: &str, start: usize) -> usize {
+    let rest = &text[start..];
+    start + rest.len() - rest.trim_start_matches([' ', '\t', '\n']).len()
+}
+
+/// A scheme of 2 to 32 characters followed by `:` and no spaces or angle brackets
+fn is_absolute_uri(text: &str) -> bool {
+    let scheme = match text.split_once(':') {
+        Some((scheme, _)) => scheme,
+        None => return false,
+    };
+
+    (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+}
+
+/// An email address as defined by the HTML spec, which CommonMark autolinks follow
+fn is_email_address(text: &str) -> bool {
+    let (local, domain) = match text.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    let valid_local = !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c));
+
+    let valid_domain = domain.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+
+    valid_local && valid_domain
+}
+
+/// Count how many times `character` repeats at the start of `text`
+fn count_run(text: &str, character: char) -> usize {
+    text.chars().take_while(|&c| c == character).count()
+}
+
+/// Turn line endings into spaces and strip one surrounding space, if any
+fn normalize_code_span(content: &str) -> String {
+    let content = content.replace('\n', " ");
+
+    if content.len() >= 2
+        && content.starts_with(' ')
+        && content.ends_with(' ')
+        && !content.chars().all(|c| c == ' ')
+    {
+        content[1..content.len() - 1].to_string()
+    } else {
+        content
+    }
+}
+
+/// ASCII punctuation, or any other non-alphanumeric symbol outside of ASCII
+fn is_punctuation(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_punctuation()
+    } else {
+        !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control()
+    }
+}
@@ -0,0 +1,126 @@
+/// A line of the document with a cursor over the part not yet consumed by
+/// container markers. The cursor keeps track of columns, so that a tab can be
+/// partly consumed as indentation (tab stops are every 4 columns)
+#[derive(Clone)]
+pub struct Line<'a> {
+    text: &'a str,
+    offset: usize,
+    column: usize,
+    partially_consumed_tab: bool,
+}
+
+impl<'a> Line<'a> {
+    pub fn new(text: &'a str) -> Line<'a> {
+        Line {
+            text,
+            offset: 0,
+            column: 0,
+            partially_consumed_tab: false,
+        }
+    }
+
+    /// The byte offset and the column of the next character that is not a space or a tab
+    fn next_nonspace(&self) -> (usize, usize) {
+        let mut offset = self.offset;
+        let mut column = self.column;
+
+        for c in self.text[self.offset..].chars() {
+            match c {
+                ' ' => column += 1,
+                '\t' => column += 4 - column % 4,
+                _ => break,
+            }
+            offset += 1;
+        }
+
+        (offset, column)
+    }
+
+    /// The column the cursor is at
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The width of the whitespace before the next non-space character
+    pub fn indent(&self) -> usize {
+        self.next_nonspace().1 - self.column
+    }
+
+    /// An indentation of 4 columns or more is for indented code
+    pub fn is_indented(&self) -> bool {
+        self.indent() >= 4
+    }
+
+    /// Whether only spaces and tabs are left
+    pub fn is_blank(&self) -> bool {
+        self.next_nonspace().0 == self.text.len()
+    }
+
+    /// The text left after the cursor
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    /// The text left after the indentation
+    pub fn rest_after_indent(&self) -> &'a str {
+        &self.text[self.next_nonspace().0..]
+    }
+
+    /// The character right after the cursor
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Move the cursor past the indentation
+    pub fn skip_indent(&mut self) {
+        let (offset, column) = self.next_nonspace();
+        self.offset = offset;
+        self.column = column;
+        self.partially_consumed_tab = false;
+    }
+
+    /// Move the cursor by `count` characters, or by `count` columns when
+    /// `columns` is set, in which case a tab may be partly consumed
+    pub fn advance(&mut self, mut count: usize, columns: bool) {
+        while count > 0 {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => break,
+            };
+
+            if c == '\t' {
+                let columns_to_tab_stop = 4 - self.column % 4;
+
+                if columns {
+                    self.partially_consumed_tab = columns_to_tab_stop > count;
+                    let columns_to_advance = columns_to_tab_stop.min(count);
+                    self.column += columns_to_advance;
+                    if !self.partially_consumed_tab {
+                        self.offset += 1;
+                    }
+                    count -= columns_to_advance;
+                } else {
+                    self.partially_consumed_tab = false;
+                    self.column += columns_to_tab_stop;
+                    self.offset += 1;
+                    count -= 1;
+                }
+            } else {
+                self.partially_consumed_tab = false;
+                self.offset += c.len_utf8();
+                self.column += 1;
+                count -= 1;
+            }
+        }
+    }
+
+    /// The text left after the cursor, with the unconsumed part of a tab as spaces
+    pub fn remaining_text(&self) -> String {
+        if self.partially_consumed_tab {
+            let columns_to_tab_stop = 4 - self.column % 4;
+            " ".repeat(columns_to_tab_stop) + &self.text[self.offset + 1..]
+        } else {
+            self.rest().to_string()
+        }
+    }
+}
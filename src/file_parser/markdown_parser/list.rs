@@ -0,0 +1,293 @@
+use super::{
+    inline_parser::{parse_inlines, print_inlines},
+    is_blank,
+    line::Line,
+    BlockElement, Continuation, DocumentContext,
+};
+
+/// The marker that starts a list item
+pub enum ListMarker {
+    Bullet(char),
+    Ordered { start: u32, delimiter: char },
+}
+
+/// An ordered or unordered list, holding `ListItem` elements
+pub struct List {
+    marker: ListMarker,
+    /// A tight list has no blank lines between its items or inside them,
+    /// and prints its paragraphs without `<p>` tags
+    tight: bool,
+    items: Vec<BlockElement>,
+    last_item_end_line: usize,
+}
+
+/// A list item, holding any block element
+pub struct ListItem {
+    /// How far the content is from the start of the line, past the marker.
+    /// Continuation lines have to be indented at least this much
+    content_indent: usize,
+    children: Vec<BlockElement>,
+    last_child_end_line: Option<usize>,
+    has_blank_line_between_children: bool,
+}
+
+impl List {
+    pub fn new(marker: ListMarker) -> List {
+        List {
+            marker,
+            tight: true,
+            items: vec![],
+            last_item_end_line: 0,
+        }
+    }
+
+    /// Whether an item with `marker` belongs to this list rather than a new one
+    pub fn continues_with(&self, marker: &ListMarker) -> bool {
+        match (&self.marker, marker) {
+            (ListMarker::Bullet(a), ListMarker::Bullet(b)) => a == b,
+            (
+                ListMarker::Ordered { delimiter: a, .. },
+                ListMarker::Ordered { delimiter: b, .. },
+            ) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Add a closed item, spanning from `start_line` to `end_line`
+    pub fn add_item(&mut self, item: BlockElement, start_line: usize, end_line: usize) {
+        if !self.items.is_empty() && self.last_item_end_line + 1 < start_line {
+            self.tight = false;
+        }
+
+        if let BlockElement::ListItem(list_item) = &item {
+            if list_item.has_blank_line_between_children {
+                self.tight = false;
+            }
+        }
+
+        self.items.push(item);
+        self.last_item_end_line = end_line;
+    }
+
+    /// The line of the end of the last item
+    pub fn end_line(&self) -> usize {
+        self.last_item_end_line
+    }
+
+    pub fn items(&self) -> &[BlockElement] {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut [BlockElement] {
+        &mut self.items
+    }
+
+    pub fn print(&self, context: &DocumentContext) -> String {
+        let (opening_tag, closing_tag) = match self.marker {
+            ListMarker::Bullet(_) => ("<ul>\n".to_string(), "</ul>\n"),
+            ListMarker::Ordered { start: 1, .. } => ("<ol>\n".to_string(), "</ol>\n"),
+            ListMarker::Ordered { start, .. } => (format!("<ol start=\"{}\">\n", start), "</ol>\n"),
+        };
+
+        let items = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                BlockElement::ListItem(list_item) => Some(list_item.print(self.tight, context)),
+                _ => None,
+            })
+            .fold(String::new(), |acc, s| acc + &s);
+
+        opening_tag + &items + closing_tag
+    }
+}
+
+impl ListItem {
+    /// Parse the marker of a list item at the cursor of `line`, moving the cursor
+    /// to the start of the item's content. An item interrupting a paragraph
+    /// cannot be empty, and if it is ordered, it has to start at 1
+    pub fn parse_start(
+        line: &mut Line,
+        interrupts_paragraph: bool,
+    ) -> Option<(ListMarker, ListItem)> {
+        let marker_offset = line.indent();
+        if marker_offset >= 4 {
+            return None;
+        }
+
+        let rest = line.rest_after_indent();
+        let (marker, marker_length) = match rest.chars().next()? {
+            c @ ('*' | '+' | '-') => (ListMarker::Bullet(c), 1),
+            _ => {
+                let digits = rest.chars().take_while(char::is_ascii_digit).count();
+                if digits == 0 || digits > 9 {
+                    return None;
+                }
+
+                let delimiter = rest[digits..]
+                    .chars()
+                    .next()
+                    .filter(|&c| c == '.' || c == ')')?;
+                let start = rest[..digits].parse().ok()?;
+
+                if interrupts_paragraph && start != 1 {
+                    return None;
+                }
+
+                (ListMarker::Ordered { start, delimiter }, digits + 1)
+            }
+        };
+
+        let after_marker = &rest[marker_length..];
+        if !after_marker.is_empty() && !after_marker.starts_with([' ', '\t']) {
+            return None;
+        }
+
+        if interrupts_paragraph && is_blank(after_marker) {
+            return None;
+        }
+
+        line.skip_indent();
+        line.advance(marker_length, true);
+
+        // the content starts after 1 to 4 spaces, unless the item starts with indented code
+        let after_marker = line.clone();
+        loop {
+            line.advance(1, true);
+            if line.column() - after_marker.column() >= 5
+                || !matches!(line.peek(), Some(' ') | Some('\t'))
+            {
+                break;
+            }
+        }
+
+        let spaces_after_marker = line.column() - after_marker.column();
+        let padding = if !(1..5).contains(&spaces_after_marker) || line.is_blank() {
+            *line = after_marker;
+            if matches!(line.peek(), Some(' ') | Some('\t')) {
+                line.advance(1, true);
+            }
+            marker_length + 1
+        } else {
+            marker_length + spaces_after_marker
+        };
+
+        let item = ListItem {
+            content_indent: marker_offset + padding,
+            children: vec![],
+            last_child_end_line: None,
+            has_blank_line_between_children: false,
+        };
+
+        Some((marker, item))
+    }
+
+    /// An item continues on blank lines, unless it is still empty,
+    /// and on lines indented past its marker
+    pub fn continuation(&self, line: &mut Line, has_open_child: bool) -> Continuation {
+        if line.is_blank() {
+            if self.children.is_empty() && !has_open_child {
+                return Continuation::Unmatched;
+            }
+
+            line.skip_indent();
+            Continuation::Matched
+        } else if line.indent() >= self.content_indent {
+            line.advance(self.content_indent, true);
+            Continuation::Matched
+        } else {
+            Continuation::Unmatched
+        }
+    }
+
+    /// Add a closed child, spanning from `start_line` to `end_line`
+    pub fn add_child(&mut self, child: BlockElement, start_line: usize, end_line: usize) {
+        if let Some(last_child_end_line) = self.last_child_end_line {
+            if last_child_end_line + 1 < start_line {
+                self.has_blank_line_between_children = true;
+            }
+        }
+
+        self.children.push(child);
+        self.last_child_end_line = Some(end_line);
+    }
+
+    pub fn children(&self) -> &[BlockElement] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut [BlockElement] {
+        &mut self.children
+    }
+
+    /// The line of the end of the last child, or the line of the marker for an empty item
+    pub fn end_line(&self, start_line: usize) -> usize {
+        self.last_child_end_line.unwrap_or(start_line)
+    }
+
+    fn print(&self, tight: bool, context: &DocumentContext) -> String {
+        let mut result = String::from("<li>");
+
+        for (i, child) in self.children.iter().enumerate() {
+            let task = match child {
+                BlockElement::Paragraph(text) if i == 0 && context.options.tasklists => {
+                    parse_task_marker(text)
+                }
+                _ => None,
+            };
+
+            match (child, task) {
+                (_, Some((checked, text))) => {
+                    let content = format!(
+                        "{} {}",
+                        print_checkbox(checked),
+                        print_inlines(&parse_inlines(text.trim(), context))
+                    );
+
+                    if tight {
+                        result += &content;
+                    } else {
+                        result += &format!("\n<p>{}</p>\n", content);
+                    }
+                }
+                (BlockElement::Paragraph(_), _) if tight => {
+                    result += &child.print_inline_content(context)
+                }
+                (_, _) => {
+                    if !result.ends_with('\n') {
+                        result.push('\n');
+                    }
+                    result += &child.print(context);
+                }
+            }
+        }
+
+        result + "</li>\n"
+    }
+}
+
+/// Parse the `[ ]` or `[x]` marker starting the first paragraph of a task list item.
+/// Returns whether the task is checked, and the text after the marker
+fn parse_task_marker(text: &str) -> Option<(bool, &str)> {
+    let checked = match text.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+
+    let rest = &text[3..];
+    if !rest.starts_with([' ', '\t', '\n']) {
+        return None;
+    }
+
+    Some((checked, rest))
+}
+
+/// The disabled checkbox of a task list item
+fn print_checkbox(checked: bool) -> &'static str {
+    if checked {
+        "<input type=\"checkbox\" checked=\"\" disabled=\"\" />"
+    } else {
+        "<input type=\"checkbox\" disabled=\"\" />"
+    }
+}
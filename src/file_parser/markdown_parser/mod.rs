@@ -0,0 +1,754 @@
+mod block_quote;
+mod code_block;
+mod entity;
+mod footnote;
+mod heading;
+mod html_block;
+mod inline_parser;
+mod line;
+mod list;
+mod raw_html;
+mod table;
+
+use block_quote::BlockQuote;
+use std::collections::HashMap;
+
+use code_block::CodeBlock;
+use footnote::{FootnoteDefinition, Footnotes};
+use heading::{print_table_of_contents, Heading};
+use html_block::HtmlBlock;
+use inline_parser::{
+    parse_inlines, parse_link_reference_definition, print_inlines, LinkReferences,
+};
+use line::Line;
+use list::{List, ListItem};
+use raw_html::sanitize_html;
+use table::Table;
+
+/// A Markdown document, parsed line by line into a tree of block elements
+pub struct MarkdownDocument {
+    /// The closed top-level elements
+    elements: Vec<BlockElement>,
+    /// The open elements with the line they start on, from the outermost
+    /// container down to the innermost element. An element is added to
+    /// its parent once it is closed
+    open_elements: Vec<(BlockElement, usize)>,
+    line_number: usize,
+    context: DocumentContext,
+}
+
+/// How a Markdown document is rendered. The default is plain CommonMark,
+/// and each GitHub-flavored Markdown extension is turned on separately
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkdownOptions {
+    /// Escape the raw HTML that is not allowed, and drop unsafe link destinations,
+    /// for content from untrusted authors
+    pub safe: bool,
+    /// Pipe tables, with a header row and a delimiter row
+    pub tables: bool,
+    /// `~~text~~` or `~text~` rendered as `<del>`
+    pub strikethrough: bool,
+    /// List items starting with `[ ]` or `[x]` rendered with a checkbox
+    pub tasklists: bool,
+    /// Bare `www.`, `http://` and `https://` URLs rendered as links
+    pub autolinks: bool,
+    /// `[^label]` references to `[^label]: text` definitions, printed
+    /// as numbered notes at the end of the document
+    pub footnotes: bool,
+    /// An `id` on every heading, from a trailing `{#custom-id}` or a slug of its text
+    pub heading_ids: bool,
+    /// A `¶` permalink after each heading that has an id
+    pub heading_anchors: bool,
+}
+
+/// What the elements need from the whole document to be printed
+struct DocumentContext {
+    link_references: LinkReferences,
+    footnotes: Footnotes,
+    options: MarkdownOptions,
+}
+
+/// Whether an open element continues on a new line
+enum Continuation {
+    Matched,
+    Unmatched,
+    /// The line ends the element, like the closing fence of a code block
+    Finished,
+}
+
+/// What kind of element starts on a line, if any
+enum BlockStart {
+    None,
+    /// A container, whose content may start other elements on the same line
+    Container,
+    /// A leaf element, that takes the rest of the line as its content
+    Leaf,
+    /// A leaf element, that takes the whole line as its opening,
+    /// like a heading or a code fence
+    WholeLine,
+}
+
+impl MarkdownDocument {
+    /// Parse a whole Markdown text
+    pub fn parse(content: &str, options: MarkdownOptions) -> MarkdownDocument {
+        let mut doc = MarkdownDocument::new(options);
+
+        for line in content.lines() {
+            doc.add_line_to_document(line);
+        }
+        doc.finish();
+
+        doc
+    }
+
+    pub fn new(options: MarkdownOptions) -> MarkdownDocument {
+        MarkdownDocument {
+            elements: vec![],
+            open_elements: vec![],
+            line_number: 0,
+            context: DocumentContext {
+                link_references: LinkReferences::new(),
+                footnotes: Footnotes::default(),
+                options,
+            },
+        }
+    }
+
+    pub fn add_line_to_document(&mut self, line: &str) {
+        self.line_number += 1;
+        let mut line = Line::new(line);
+
+        // find how many of the open elements continue on this line
+        let mut matched = 0;
+        while matched < self.open_elements.len() {
+            let has_open_child = matched + 1 < self.open_elements.len();
+
+            match self.open_elements[matched]
+                .0
+                .continuation(&mut line, has_open_child)
+            {
+                Continuation::Matched => matched += 1,
+                Continuation::Unmatched => break,
+                Continuation::Finished => {
+                    self.close_innermost_element(self.line_number);
+                    return;
+                }
+            }
+        }
+
+        let mut unmatched_elements_closed = matched == self.open_elements.len();
+        let mut depth = matched;
+
+        // open the new elements starting on this line
+        let matched_leaf = depth > 0 && self.open_elements[depth - 1].0.takes_any_line();
+        if !matched_leaf {
+            loop {
+                match self.start_element(&mut line, depth) {
+                    BlockStart::None => break,
+                    BlockStart::Container => {
+                        depth = self.open_elements.len();
+                        unmatched_elements_closed = true;
+                    }
+                    BlockStart::Leaf => {
+                        depth = self.open_elements.len();
+                        unmatched_elements_closed = true;
+                        break;
+                    }
+                    BlockStart::WholeLine => return,
+                }
+            }
+        }
+
+        // a paragraph continues lazily on a line that starts nothing else
+        if !unmatched_elements_closed && !line.is_blank() && self.innermost_is_paragraph() {
+            self.add_text_to_innermost_element(&line);
+            return;
+        }
+
+        self.close_unmatched_elements(depth);
+
+        let innermost_accepts_lines = matches!(
+            self.open_elements.last(),
+            Some((BlockElement::Paragraph(_), _))
+                | Some((BlockElement::CodeBlock(_), _))
+                | Some((BlockElement::HtmlBlock(_), _))
+                | Some((BlockElement::Table(_), _))
+        );
+
+        if innermost_accepts_lines {
+            self.add_text_to_innermost_element(&line);
+        } else if !line.is_blank() {
+            line.skip_indent();
+            self.open_element(BlockElement::Paragraph(String::new()));
+            self.add_text_to_innermost_element(&line);
+        }
+    }
+
+    /// Close the elements still open at the end of the document.
+    /// Call it after the last line has been added, before printing
+    pub fn finish(&mut self) {
+        while !self.open_elements.is_empty() {
+            self.close_innermost_element(self.line_number);
+        }
+
+        if self.context.options.heading_ids {
+            assign_heading_ids(&mut self.elements, &mut HashMap::new(), &self.context);
+        }
+    }
+
+    /// The text of the first level-1 heading, or else of the first heading.
+    /// Headings without text are skipped
+    pub fn title(&self) -> Option<String> {
+        let mut headings = vec![];
+        collect_headings(&self.elements, &mut headings);
+
+        let titles: Vec<(usize, String)> = headings
+            .iter()
+            .map(|heading| (heading.level(), heading.plain_text(&self.context)))
+            .filter(|(_, text)| !text.trim().is_empty())
+            .collect();
+
+        titles
+            .iter()
+            .find(|(level, _)| *level == 1)
+            .or_else(|| titles.first())
+            .map(|(_, text)| text.clone())
+    }
+
+    /// A nested `<nav>` of links to the headings of the document, or nothing
+    /// if it has no headings. Headings need ids to be linked to
+    pub fn table_of_contents(&self) -> String {
+        let mut headings = vec![];
+        collect_headings(&self.elements, &mut headings);
+
+        print_table_of_contents(&headings, &self.context)
+    }
+
+    pub fn print(&self) -> String {
+        self.context.footnotes.clear_references();
+
+        let mut result = String::new();
+        for element in self.elements.iter() {
+            result += element.print(&self.context).as_str();
+        }
+        result + &self.context.footnotes.print(&self.context)
+    }
+
+    /// Try opening a new element at the cursor of `line`, inside the first
+    /// `depth` open elements
+    fn start_element(&mut self, line: &mut Line, depth: usize) -> BlockStart {
+        let container_is_paragraph =
+            depth > 0 && matches!(self.open_elements[depth - 1].0, BlockElement::Paragraph(_));
+
+        if let Some(block_quote) = BlockQuote::parse_start(line) {
+            self.close_unmatched_elements(depth);
+            self.open_element(BlockElement::BlockQuote(block_quote));
+            return BlockStart::Container;
+        }
+
+        if !line.is_indented() {
+            if let Some(heading) = BlockElement::to_heading(line.rest_after_indent()) {
+                self.close_unmatched_elements(depth);
+                self.open_element(heading);
+                return BlockStart::WholeLine;
+            }
+
+            if let Some(code_block) =
+                CodeBlock::from_opening_fence(line.rest_after_indent(), line.indent())
+            {
+                self.close_unmatched_elements(depth);
+                self.open_element(BlockElement::CodeBlock(code_block));
+                return BlockStart::WholeLine;
+            }
+
+            let interrupts_paragraph = container_is_paragraph || self.innermost_is_paragraph();
+            if let Some(html_block) =
+                HtmlBlock::from_start(line.rest_after_indent(), interrupts_paragraph)
+            {
+                self.close_unmatched_elements(depth);
+                self.open_element(BlockElement::HtmlBlock(html_block));
+                return BlockStart::Leaf;
+            }
+
+            if self.context.options.footnotes {
+                if let Some(definition) = FootnoteDefinition::parse_start(line) {
+                    self.close_unmatched_elements(depth);
+                    self.open_element(BlockElement::FootnoteDefinition(definition));
+                    return BlockStart::Container;
+                }
+            }
+
+            if container_is_paragraph {
+                if let Some(heading_level) = setext_heading_level(line.rest_after_indent()) {
+                    if self.underline_paragraph(depth, heading_level) {
+                        return BlockStart::WholeLine;
+                    }
+                }
+            }
+
+            if is_thematic_break(line.rest_after_indent()) {
+                self.close_unmatched_elements(depth);
+                self.open_element(BlockElement::ThematicBreak);
+                return BlockStart::WholeLine;
+            }
+        }
+
+        if let Some((marker, item)) = ListItem::parse_start(line, container_is_paragraph) {
+            self.close_unmatched_elements(depth);
+
+            let continues_list = matches!(
+                self.open_elements.last(),
+                Some((BlockElement::List(list), _)) if list.continues_with(&marker)
+            );
+            if !continues_list {
+                self.open_element(BlockElement::List(List::new(marker)));
+            }
+
+            self.open_element(BlockElement::ListItem(item));
+            return BlockStart::Container;
+        }
+
+        // an indented code block cannot interrupt a paragraph, even a lazy one
+        if line.is_indented() && !line.is_blank() && !self.innermost_is_paragraph() {
+            line.advance(4, true);
+            self.close_unmatched_elements(depth);
+            self.open_element(BlockElement::CodeBlock(CodeBlock::indented()));
+            return BlockStart::Leaf;
+        }
+
+        // a table is only tried once no other element starts on the line
+        if self.context.options.tables
+            && !line.is_indented()
+            && container_is_paragraph
+            && self.start_table(line, depth)
+        {
+            return BlockStart::WholeLine;
+        }
+
+        BlockStart::None
+    }
+
+    /// Turn the paragraph at `depth` into a setext heading. Its link reference
+    /// definitions are taken out first, and if nothing is left, it cannot be a heading
+    fn underline_paragraph(&mut self, depth: usize, heading_level: usize) -> bool {
+        self.close_unmatched_elements(depth);
+
+        let paragraph = match self.open_elements.last_mut() {
+            Some((paragraph, _)) => paragraph,
+            None => return false,
+        };
+
+        paragraph.extract_link_references(&mut self.context.link_references);
+
+        let heading_text = match paragraph {
+            BlockElement::Paragraph(text) if !text.trim().is_empty() => text.trim().to_string(),
+            _ => return false,
+        };
+
+        *paragraph = BlockElement::Heading(Heading::new(heading_level, heading_text));
+        true
+    }
+
+    /// Turn the last line of the paragraph at `depth` into the header of a table,
+    /// if `line` is a delimiter row with as many cells
+    fn start_table(&mut self, line: &Line, depth: usize) -> bool {
+        let table = match &self.open_elements[depth - 1].0 {
+            BlockElement::Paragraph(text) => {
+                let header = text.trim_end_matches('\n').rsplit('\n').next();
+                header.and_then(|header| Table::from_header(header, line.rest_after_indent()))
+            }
+            _ => None,
+        };
+
+        let table = match table {
+            Some(table) => table,
+            None => return false,
+        };
+
+        self.close_unmatched_elements(depth);
+
+        if let Some((BlockElement::Paragraph(text), _)) = self.open_elements.last_mut() {
+            let header_start = text.trim_end_matches('\n').rfind('\n').map_or(0, |i| i + 1);
+            text.truncate(header_start);
+        }
+
+        // the lines before the header stay a paragraph, which ends right before it
+        self.close_innermost_element(self.line_number - 2);
+        self.open_elements
+            .push((BlockElement::Table(table), self.line_number - 1));
+
+        true
+    }
+
+    fn innermost_is_paragraph(&self) -> bool {
+        matches!(
+            self.open_elements.last(),
+            Some((BlockElement::Paragraph(_), _))
+        )
+    }
+
+    /// Open a new element, closing the innermost elements that cannot contain it
+    fn open_element(&mut self, element: BlockElement) {
+        while let Some((innermost, _)) = self.open_elements.last() {
+            if innermost.can_contain(&element) {
+                break;
+            }
+            self.close_innermost_element(self.line_number - 1);
+        }
+
+        self.open_elements.push((element, self.line_number));
+    }
+
+    /// Close the open elements past the first `depth` ones
+    fn close_unmatched_elements(&mut self, depth: usize) {
+        while self.open_elements.len() > depth {
+            self.close_innermost_element(self.line_number - 1);
+        }
+    }
+
+    /// Close the innermost open element, ending on `end_line`,
+    /// and add it to its parent
+    fn close_innermost_element(&mut self, end_line: usize) {
+        let (mut element, start_line) = match self.open_elements.pop() {
+            Some(open_element) => open_element,
+            None => return,
+        };
+
+        let end_line = element.close(&mut self.context.link_references, start_line, end_line);
+
+        // definitions are kept apart, to be printed at the end of the document
+        if let BlockElement::FootnoteDefinition(definition) = element {
+            self.context.footnotes.define(definition);
+            return;
+        }
+
+        if element.is_empty() {
+            return;
+        }
+
+        match self.open_elements.last_mut() {
+            Some((parent, _)) => parent.add_child(element, start_line, end_line),
+            None => self.elements.push(element),
+        }
+    }
+
+    /// Add what is left of `line` to the innermost open element
+    fn add_text_to_innermost_element(&mut self, line: &Line) {
+        match self.open_elements.last_mut() {
+            Some((BlockElement::Paragraph(text), _)) => {
+                text.push_str(&line.remaining_text());
+                text.push('\n');
+            }
+            Some((BlockElement::CodeBlock(code_block), _)) => {
+                code_block.add_line(&line.remaining_text())
+            }
+            Some((BlockElement::Table(table), _)) => table.add_row(&line.remaining_text()),
+            Some((BlockElement::HtmlBlock(html_block), _)) => {
+                html_block.add_line(&line.remaining_text());
+                if html_block.is_finished() {
+                    self.close_innermost_element(self.line_number);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A block element. Leaf elements hold the raw text of their inline content,
+/// and containers hold other block elements
+enum BlockElement {
+    Heading(Heading),
+    Paragraph(String),
+    ThematicBreak,
+    CodeBlock(CodeBlock),
+    HtmlBlock(HtmlBlock),
+    Table(Table),
+    BlockQuote(BlockQuote),
+    List(List),
+    ListItem(ListItem),
+    FootnoteDefinition(FootnoteDefinition),
+}
+
+impl BlockElement {
+    fn to_heading(line: &str) -> Option<BlockElement> {
+        let relevant_line_portion = trim_start_at_most(line, ' ', 3);
+        if !relevant_line_portion.starts_with('#') {
+            return None;
+        }
+
+        let text_after_hashtag_run = trim_start_at_most(relevant_line_portion, '#', 6);
+
+        if !text_after_hashtag_run.is_empty() && !text_after_hashtag_run.starts_with([' ', '\t']) {
+            return None;
+        }
+
+        let heading_level = relevant_line_portion.len() - text_after_hashtag_run.len();
+
+        let trailing_whitespace_trimmed_heading = text_after_hashtag_run.trim_matches([' ', '\t']);
+
+        let trailing_hashtag_trimmed_heading =
+            trailing_whitespace_trimmed_heading.trim_end_matches('#');
+
+        // the closing run of `#` has to be preceded by a space, so `foo#` and `foo \#`
+        // keep their `#`, while a heading of only `#` is empty
+        if !trailing_hashtag_trimmed_heading.is_empty()
+            && !trailing_hashtag_trimmed_heading.ends_with([' ', '\t'])
+        {
+            return Some(BlockElement::Heading(Heading::new(
+                heading_level,
+                trailing_whitespace_trimmed_heading.to_string(),
+            )));
+        }
+
+        Some(BlockElement::Heading(Heading::new(
+            heading_level,
+            trailing_hashtag_trimmed_heading
+                .trim_end_matches([' ', '\t'])
+                .to_string(),
+        )))
+    }
+
+    /// Whether the open element continues on `line`,
+    /// moving the cursor past the element's own markers
+    fn continuation(&self, line: &mut Line, has_open_child: bool) -> Continuation {
+        match self {
+            BlockElement::Heading(_) | BlockElement::ThematicBreak => Continuation::Unmatched,
+            BlockElement::Paragraph(_) if line.is_blank() => Continuation::Unmatched,
+            BlockElement::Paragraph(_) => Continuation::Matched,
+            BlockElement::Table(_) => Table::continuation(line),
+            BlockElement::CodeBlock(code_block) => code_block.continuation(line),
+            BlockElement::HtmlBlock(html_block) => html_block.continuation(line),
+            BlockElement::BlockQuote(block_quote) => block_quote.continuation(line),
+            BlockElement::List(_) => Continuation::Matched,
+            BlockElement::ListItem(item) => item.continuation(line, has_open_child),
+            BlockElement::FootnoteDefinition(definition) => definition.continuation(line),
+        }
+    }
+
+    /// Leaf elements that take any line they continue on, without looking
+    /// for other elements starting in it
+    fn takes_any_line(&self) -> bool {
+        matches!(
+            self,
+            BlockElement::CodeBlock(_) | BlockElement::HtmlBlock(_)
+        )
+    }
+
+    fn can_contain(&self, child: &BlockElement) -> bool {
+        match self {
+            BlockElement::BlockQuote(_) | BlockElement::FootnoteDefinition(_) => {
+                !matches!(child, BlockElement::ListItem(_))
+            }
+            BlockElement::List(_) => matches!(child, BlockElement::ListItem(_)),
+            BlockElement::ListItem(_) => !matches!(child, BlockElement::ListItem(_)),
+            _ => false,
+        }
+    }
+
+    /// The block elements held by a container, in document order
+    fn children(&self) -> &[BlockElement] {
+        match self {
+            BlockElement::BlockQuote(block_quote) => block_quote.children(),
+            BlockElement::List(list) => list.items(),
+            BlockElement::ListItem(item) => item.children(),
+            _ => &[],
+        }
+    }
+
+    fn children_mut(&mut self) -> &mut [BlockElement] {
+        match self {
+            BlockElement::BlockQuote(block_quote) => block_quote.children_mut(),
+            BlockElement::List(list) => list.items_mut(),
+            BlockElement::ListItem(item) => item.children_mut(),
+            _ => &mut [],
+        }
+    }
+
+    fn add_child(&mut self, child: BlockElement, start_line: usize, end_line: usize) {
+        match self {
+            BlockElement::BlockQuote(block_quote) => block_quote.add_child(child),
+            BlockElement::List(list) => list.add_item(child, start_line, end_line),
+            BlockElement::ListItem(item) => item.add_child(child, start_line, end_line),
+            BlockElement::FootnoteDefinition(definition) => definition.add_child(child),
+            _ => {}
+        }
+    }
+
+    /// Finish an open element once no more lines can be added to it,
+    /// and return the line it actually ends on
+    fn close(
+        &mut self,
+        link_references: &mut LinkReferences,
+        start_line: usize,
+        end_line: usize,
+    ) -> usize {
+        match self {
+            BlockElement::Paragraph(_) => {
+                self.extract_link_references(link_references);
+                end_line
+            }
+            BlockElement::CodeBlock(code_block) => end_line - code_block.close(),
+            BlockElement::List(list) => list.end_line(),
+            BlockElement::ListItem(item) => item.end_line(start_line),
+            BlockElement::Heading(_)
+            | BlockElement::ThematicBreak
+            | BlockElement::HtmlBlock(_)
+            | BlockElement::Table(_)
+            | BlockElement::BlockQuote(_)
+            | BlockElement::FootnoteDefinition(_) => end_line,
+        }
+    }
+
+    /// Remove the link reference definitions at the start of a paragraph,
+    /// keeping the first definition of each label
+    fn extract_link_references(&mut self, link_references: &mut LinkReferences) {
+        if let BlockElement::Paragraph(text) = self {
+            let mut start = 0;
+
+            while let Some((label, reference, end)) =
+                parse_link_reference_definition(&text[start..])
+            {
+                link_references.entry(label).or_insert(reference);
+                start += end;
+            }
+
+            text.replace_range(..start, "");
+        }
+    }
+
+    /// A paragraph is left empty when it only held link reference definitions
+    fn is_empty(&self) -> bool {
+        matches!(self, BlockElement::Paragraph(text) if text.trim().is_empty())
+    }
+
+    pub fn print(&self, context: &DocumentContext) -> String {
+        match self {
+            BlockElement::Heading(heading) => heading.print(context),
+            BlockElement::Paragraph(paragraph_text) => {
+                format!(
+                    "<p>{}</p>\n",
+                    print_inlines(&parse_inlines(paragraph_text.trim_end(), context))
+                )
+            }
+            BlockElement::ThematicBreak => "<hr />\n".to_string(),
+            BlockElement::CodeBlock(code_block) => code_block.print(),
+            BlockElement::HtmlBlock(html_block) if context.options.safe => {
+                sanitize_html(html_block.content())
+            }
+            BlockElement::HtmlBlock(html_block) => html_block.content().to_string(),
+            BlockElement::Table(table) => table.print(context),
+            BlockElement::BlockQuote(block_quote) => block_quote.print(context),
+            BlockElement::List(list) => list.print(context),
+            BlockElement::ListItem(_) | BlockElement::FootnoteDefinition(_) => String::new(),
+        }
+    }
+
+    /// The inline content of a paragraph without the `<p>` tags,
+    /// as printed in a tight list
+    fn print_inline_content(&self, context: &DocumentContext) -> String {
+        match self {
+            BlockElement::Paragraph(paragraph_text) => {
+                print_inlines(&parse_inlines(paragraph_text.trim_end(), context))
+            }
+            _ => self.print(context),
+        }
+    }
+}
+
+/// Give the headings of `elements` and of their children an id each, in document order
+fn assign_heading_ids(
+    elements: &mut [BlockElement],
+    used_ids: &mut HashMap<String, usize>,
+    context: &DocumentContext,
+) {
+    for element in elements.iter_mut() {
+        match element {
+            BlockElement::Heading(heading) => heading.assign_id(used_ids, context),
+            _ => assign_heading_ids(element.children_mut(), used_ids, context),
+        }
+    }
+}
+
+/// Collect the headings of `elements` and of their children, in document order
+fn collect_headings<'a>(elements: &'a [BlockElement], headings: &mut Vec<&'a Heading>) {
+    for element in elements.iter() {
+        match element {
+            BlockElement::Heading(heading) => headings.push(heading),
+            _ => collect_headings(element.children(), headings),
+        }
+    }
+}
+
+/// The level of a setext heading underlined by `line`: 1 for `=` and 2 for `-`
+fn setext_heading_level(line: &str) -> Option<usize> {
+    let underline = line.trim_end_matches([' ', '\t']);
+
+    if !underline.is_empty() && underline.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !underline.is_empty() && underline.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Whether `line` is 3 or more matching `-`, `*` or `_`, with only spaces
+/// and tabs between them
+fn is_thematic_break(line: &str) -> bool {
+    let character = match line.chars().next() {
+        Some(c @ ('-' | '*' | '_')) => c,
+        _ => return false,
+    };
+
+    let mut count = 0;
+    for c in line.chars() {
+        match c {
+            ' ' | '\t' => {}
+            _ if c == character => count += 1,
+            _ => return false,
+        }
+    }
+
+    count >= 3
+}
+
+/// Percent-encode the characters that are not allowed in a URL,
+/// keeping the existing `%XX` escapes as they are
+fn normalize_url(url: &str) -> String {
+    let mut normalized = String::with_capacity(url.len());
+    let bytes = url.as_bytes();
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        let is_escape = byte == b'%'
+            && bytes.len() > i + 2
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+
+        if byte.is_ascii_alphanumeric() || b"-_.!~*'();/?:@&=+$,#".contains(&byte) || is_escape {
+            normalized.push(byte as char);
+        } else {
+            normalized.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    normalized
+}
+
+/// Whether the line only has spaces and tabs
+fn is_blank(line: &str) -> bool {
+    line.trim_matches([' ', '\t']).is_empty()
+}
+
+fn trim_start_at_most(line: &str, character_to_skip: char, number_of_times: usize) -> &str {
+    let mut start_index = 0;
+
+    for c in line.chars() {
+        if start_index == number_of_times || c != character_to_skip {
+            break;
+        }
+
+        start_index += 1;
+    }
+
+    &line[start_index..line.len()]
+}
@@ -0,0 +1,296 @@
+use super::entity::decode_entities;
+use super::inline_parser::skip_whitespace;
+use crate::file_parser::escape::escape_html;
+
+/// The tags kept by the safe mode. Any other tag is escaped
+const ALLOWED_TAGS: [&str; 52] = [
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+    "var",
+];
+
+/// The attributes kept on allowed tags by the safe mode. Any other attribute is dropped
+const ALLOWED_ATTRIBUTES: [&str; 14] = [
+    "align", "alt", "cite", "class", "colspan", "height", "href", "id", "lang", "open", "rowspan",
+    "src", "title", "width",
+];
+
+/// The URL schemes kept by the safe mode in links, images and URL attributes
+const SAFE_URL_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// An open or a closing HTML tag
+struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, Option<&'a str>)>,
+    is_closing: bool,
+    is_self_closing: bool,
+    length: usize,
+}
+
+/// The length of the raw HTML at the start of `text`: an open or a closing tag,
+/// a comment, a processing instruction, a declaration or a CDATA section
+pub fn parse_raw_html(text: &str) -> Option<usize> {
+    parse_tag_with_attributes(text)
+        .map(|tag| tag.length)
+        .or_else(|| parse_comment_like(text))
+}
+
+/// The name and the length of the open or closing tag at the start of `text`
+pub fn parse_tag(text: &str) -> Option<(&str, usize)> {
+    parse_tag_with_attributes(text).map(|tag| (tag.name, tag.length))
+}
+
+/// Parse an open tag like `<a href="url">` or a closing tag like `</a>`
+fn parse_tag_with_attributes(text: &str) -> Option<Tag<'_>> {
+    let (is_closing, name_start) = if text.starts_with("</") {
+        (true, 2)
+    } else if text.starts_with('<') {
+        (false, 1)
+    } else {
+        return None;
+    };
+
+    let name_length = text[name_start..]
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or(text.len() - name_start);
+    let name = &text[name_start..name_start + name_length];
+
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut position = name_start + name_length;
+    let mut attributes = vec![];
+
+    if !is_closing {
+        while let Some((attribute, end)) = parse_attribute(text, position) {
+            attributes.push(attribute);
+            position = end;
+        }
+    }
+
+    position = skip_whitespace(text, position);
+
+    let is_self_closing = !is_closing && text[position..].starts_with('/');
+    if is_self_closing {
+        position += 1;
+    }
+
+    if !text[position..].starts_with('>') {
+        return None;
+    }
+
+    Some(Tag {
+        name,
+        attributes,
+        is_closing,
+        is_self_closing,
+        length: position + 1,
+    })
+}
+
+/// Parse an attribute with its optional value, after the whitespace at `start`.
+/// Returns the attribute and the position after it
+fn parse_attribute(text: &str, start: usize) -> Option<((&str, Option<&str>), usize)> {
+    let name_start = skip_whitespace(text, start);
+    if name_start == start {
+        return None;
+    }
+
+    let rest = &text[name_start..];
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':') {
+        return None;
+    }
+
+    let name_length = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-')))
+        .unwrap_or(rest.len());
+    let name = &rest[..name_length];
+    let name_end = name_start + name_length;
+
+    let equals = skip_whitespace(text, name_end);
+    if !text[equals..].starts_with('=') {
+        return Some(((name, None), name_end));
+    }
+
+    let value_start = skip_whitespace(text, equals + 1);
+    let rest = &text[value_start..];
+
+    let (value, value_end) = match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let length = rest[1..].find(quote)?;
+            (&rest[1..length + 1], value_start + length + 2)
+        }
+        _ => {
+            let length = rest
+                .find(|c: char| {
+                    matches!(c, '"' | '\'' | '=' | '<' | '>' | '`') || c.is_ascii_whitespace()
+                })
+                .unwrap_or(rest.len());
+            if length == 0 {
+                return None;
+            }
+            (&rest[..length], value_start + length)
+        }
+    };
+
+    Some(((name, Some(value)), value_end))
+}
+
+/// Parse a comment, a processing instruction, a declaration or a CDATA section,
+/// and return its length
+fn parse_comment_like(text: &str) -> Option<usize> {
+    if text.starts_with("<!-->") {
+        Some(5)
+    } else if text.starts_with("<!--->") {
+        Some(6)
+    } else if let Some(rest) = text.strip_prefix("<!--") {
+        rest.find("-->").map(|end| end + 7)
+    } else if let Some(rest) = text.strip_prefix("<?") {
+        rest.find("?>").map(|end| end + 4)
+    } else if let Some(rest) = text.strip_prefix("<![CDATA[") {
+        rest.find("]]>").map(|end| end + 12)
+    } else if let Some(rest) = text.strip_prefix("<!") {
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        rest.find('>').map(|end| end + 3)
+    } else {
+        None
+    }
+}
+
+/// Rewrite raw HTML for the safe mode. Allowed tags are kept with their allowed
+/// attributes, and everything else that looks like HTML is escaped
+pub fn sanitize_html(html: &str) -> String {
+    let mut sanitized = String::with_capacity(html.len());
+    let mut position = 0;
+
+    while let Some(offset) = html[position..].find('<') {
+        sanitized.push_str(&html[position..position + offset]);
+        position += offset;
+
+        let rest = &html[position..];
+        match parse_tag_with_attributes(rest) {
+            Some(tag) if is_allowed_tag(tag.name) => {
+                sanitized.push_str(&print_allowed_tag(&tag));
+                position += tag.length;
+            }
+            Some(tag) => {
+                sanitized.push_str(&escape_html(&rest[..tag.length]));
+                position += tag.length;
+            }
+            None => {
+                let length = parse_comment_like(rest).unwrap_or(1);
+                sanitized.push_str(&escape_html(&rest[..length]));
+                position += length;
+            }
+        }
+    }
+
+    sanitized + &html[position..]
+}
+
+fn is_allowed_tag(name: &str) -> bool {
+    ALLOWED_TAGS.contains(&name.to_ascii_lowercase().as_str())
+}
+
+/// Print a tag again with only its allowed attributes, and only safe URLs
+fn print_allowed_tag(tag: &Tag) -> String {
+    let name = tag.name.to_ascii_lowercase();
+
+    if tag.is_closing {
+        return format!("</{}>", name);
+    }
+
+    let mut printed = format!("<{}", name);
+
+    for (attribute, value) in tag.attributes.iter() {
+        let attribute = attribute.to_ascii_lowercase();
+
+        if !ALLOWED_ATTRIBUTES.contains(&attribute.as_str()) {
+            continue;
+        }
+
+        let value = match value {
+            Some(value) => decode_entities(value),
+            None => {
+                printed += &format!(" {}", attribute);
+                continue;
+            }
+        };
+
+        if matches!(attribute.as_str(), "href" | "src" | "cite") && !is_safe_url(&value) {
+            continue;
+        }
+
+        printed += &format!(" {}=\"{}\"", attribute, escape_html(&value));
+    }
+
+    if tag.is_self_closing {
+        printed += " /";
+    }
+
+    printed + ">"
+}
+
+/// A URL is safe when it is relative, or when its scheme is one of the safe schemes
+pub fn is_safe_url(url: &str) -> bool {
+    let url = url.trim_start_matches(|c: char| c.is_ascii_whitespace() || c.is_control());
+
+    let scheme_end = match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => end,
+        _ => return true,
+    };
+
+    SAFE_URL_SCHEMES.contains(&url[..scheme_end].to_ascii_lowercase().as_str())
+}
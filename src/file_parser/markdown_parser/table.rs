@@ -0,0 +1,154 @@
+use super::{
+    inline_parser::{parse_inlines, print_inlines},
+    line::Line,
+    Continuation, DocumentContext,
+};
+
+/// The alignment of a table column, set by the `:` of the delimiter row
+#[derive(Clone, Copy)]
+enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// A GitHub-flavored Markdown pipe table. Cells keep their raw text,
+/// which is parsed into inline elements when printed
+pub struct Table {
+    alignments: Vec<Alignment>,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Create a table if `header` and `delimiter` are a header row followed by
+    /// a delimiter row with as many cells, like `| a | b |` and `| :-- | --: |`
+    pub fn from_header(header: &str, delimiter: &str) -> Option<Table> {
+        let alignments = parse_delimiter_row(delimiter)?;
+        let header = split_row(header);
+
+        if header.len() != alignments.len() {
+            return None;
+        }
+
+        Some(Table {
+            alignments,
+            header,
+            rows: vec![],
+        })
+    }
+
+    /// A table continues on any line that has cells, until a blank line
+    /// or another element starting
+    pub fn continuation(line: &Line) -> Continuation {
+        if line.is_blank() || line.rest_after_indent().trim_end() == "|" {
+            Continuation::Unmatched
+        } else {
+            Continuation::Matched
+        }
+    }
+
+    /// Add a body row. Extra cells are dropped and missing cells are left empty
+    pub fn add_row(&mut self, text: &str) {
+        let mut row = split_row(text);
+        row.resize(self.alignments.len(), String::new());
+        self.rows.push(row);
+    }
+
+    pub fn print(&self, context: &DocumentContext) -> String {
+        let mut result = String::from("<table>\n<thead>\n");
+        result += &self.print_row(&self.header, "th", context);
+        result += "</thead>\n";
+
+        if !self.rows.is_empty() {
+            result += "<tbody>\n";
+            for row in self.rows.iter() {
+                result += &self.print_row(row, "td", context);
+            }
+            result += "</tbody>\n";
+        }
+
+        result + "</table>\n"
+    }
+
+    fn print_row(&self, cells: &[String], tag: &str, context: &DocumentContext) -> String {
+        let mut result = String::from("<tr>\n");
+
+        for (cell, alignment) in cells.iter().zip(self.alignments.iter()) {
+            let style = match alignment {
+                Alignment::None => "",
+                Alignment::Left => " style=\"text-align: left\"",
+                Alignment::Center => " style=\"text-align: center\"",
+                Alignment::Right => " style=\"text-align: right\"",
+            };
+
+            result += &format!(
+                "<{tag}{style}>{inner}</{tag}>\n",
+                tag = tag,
+                style = style,
+                inner = print_inlines(&parse_inlines(cell, context))
+            );
+        }
+
+        result + "</tr>\n"
+    }
+}
+
+/// Parse a delimiter row into the alignments of its columns. The row has
+/// to hold a pipe, so that a lone `---` stays a setext heading underline
+fn parse_delimiter_row(text: &str) -> Option<Vec<Alignment>> {
+    if !text.contains('|') {
+        return None;
+    }
+
+    split_row(text)
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Alignment::Center,
+                (true, false) => Alignment::Left,
+                (false, true) => Alignment::Right,
+                (false, false) => Alignment::None,
+            })
+        })
+        .collect()
+}
+
+/// Split a row on its unescaped pipes, ignoring a leading and a trailing pipe.
+/// A pipe right after a backslash is escaped, and `\|` is unescaped to `|`
+/// before the cell is parsed, so that it works even inside code spans
+fn split_row(text: &str) -> Vec<String> {
+    let text = text.trim_matches([' ', '\t']);
+    let text = text.strip_prefix('|').unwrap_or(text);
+
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+
+    // a trailing pipe does not start another cell
+    if !cell.trim_matches([' ', '\t']).is_empty() || cells.is_empty() {
+        cells.push(cell);
+    }
+
+    cells
+        .iter()
+        .map(|cell| cell.trim_matches([' ', '\t']).to_string())
+        .collect()
+}
@@ -1,3 +1,6 @@
+pub mod escape;
+pub mod front_matter;
 pub mod source_file;
+pub mod template_engine;
 pub mod template_file;
 pub mod markdown_parser;
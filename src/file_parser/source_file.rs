@@ -1,13 +1,17 @@
 use std::{
     ffi::OsStr,
-    fs::{self, File},
-    io::ErrorKind,
+    fs,
     path::{Path, PathBuf},
 };
 
+use crate::error::{Operation, PaperError};
+use crate::file_parser::front_matter::FrontMatter;
+
 /// Represents the parsed source file
 pub struct SourceFile {
+    /// The content, without the front matter
     content: String,
+    front_matter: FrontMatter,
     file_name: String,
     file_stem: String,
     ext: String,
@@ -15,14 +19,18 @@ pub struct SourceFile {
 
 impl SourceFile {
     /// Create a new `SourceFile` with parsed content and metadata
-    pub fn new(file_path: &PathBuf) -> Result<SourceFile, &str> {
-        let content = match fs::read_to_string(file_path) {
-            Ok(string) => string,
-            Err(error) => match error.kind() {
-                ErrorKind::NotFound => return Err("File does not exist"),
-                _ => return Err("Fail to open file"),
-            },
-        };
+    pub fn new(file_path: &PathBuf) -> Result<SourceFile, PaperError> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|error| PaperError::io(file_path, Operation::ReadFile, error))?;
+
+        SourceFile::from_content(file_path, &content)
+    }
+
+    /// Create a new `SourceFile` from the content of a file at `file_path`,
+    /// without reading the file
+    pub fn from_content(file_path: &Path, content: &str) -> Result<SourceFile, PaperError> {
+        let (front_matter, content) = FrontMatter::parse(file_path, content)?;
+        let content = content.to_string();
 
         let file_stem = parse_os_str_to_string(file_path.file_stem());
         let ext = parse_os_str_to_string(file_path.extension());
@@ -30,6 +38,7 @@ impl SourceFile {
 
         Ok(SourceFile {
             content,
+            front_matter,
             file_stem,
             file_name,
             ext,
@@ -41,6 +50,16 @@ impl SourceFile {
         self.content.as_str()
     }
 
+    /// Get a reference to the text file's front matter.
+    pub fn front_matter(&self) -> &FrontMatter {
+        &self.front_matter
+    }
+
+    /// Fill in the front matter keys that the file does not set with `defaults`
+    pub fn apply_defaults(&mut self, defaults: &FrontMatter) {
+        self.front_matter.apply_defaults(defaults);
+    }
+
     /// Get a reference to the text file's file name.
     pub fn file_stem(&self) -> &str {
         self.file_stem.as_str()
@@ -51,6 +70,7 @@ impl SourceFile {
         self.ext.as_str()
     }
 
+    /// Get a reference to the text file's file name, with its extension.
     pub fn file_name(&self) -> &str {
         self.file_name.as_str()
     }
@@ -58,9 +78,8 @@ impl SourceFile {
 
 /// Try parsing the os_str, fallback to empty string
 fn parse_os_str_to_string(os_str: Option<&OsStr>) -> String {
-    match os_str.and_then(OsStr::to_str) {
-        Some(string) => string,
-        None => "",
-    }
-    .to_string()
+    os_str
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_string()
 }
@@ -0,0 +1,308 @@
+use super::value::Value;
+
+/// An expression inside `{{ }}`, `{% if %}` or `{% for %}`
+pub enum Expression {
+    /// A dotted path like `page.title`
+    Variable(Vec<String>),
+    Literal(Value),
+    Not(Box<Expression>),
+    Equal(Box<Expression>, Box<Expression>),
+    NotEqual(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Filter(Box<Expression>, Filter),
+}
+
+/// A function applied to a value with `value | filter`
+pub enum Filter {
+    /// Print the value as HTML, without escaping
+    Safe,
+    /// Lowercase the value
+    Lower,
+    /// Uppercase the value
+    Upper,
+    /// Count the items of a list or table, or the characters of a text
+    Length,
+}
+
+#[derive(PartialEq)]
+enum Token {
+    Name(String),
+    String(String),
+    Integer(i64),
+    Equal,
+    NotEqual,
+    Pipe,
+    Open,
+    Close,
+}
+
+impl Expression {
+    /// Parse the text of an expression
+    pub fn parse(text: &str) -> Result<Expression, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+
+        let expression = parser.parse_or()?;
+        if parser.position < parser.tokens.len() {
+            return Err(format!("Unexpected text in expression `{}`", text));
+        }
+
+        Ok(expression)
+    }
+
+    /// Evaluate the expression, looking up variables with `lookup`.
+    /// None means it uses a variable that is not defined
+    pub fn evaluate(&self, lookup: &dyn Fn(&[String]) -> Option<Value>) -> Option<Value> {
+        match self {
+            Expression::Variable(path) => lookup(path),
+            Expression::Literal(value) => Some(value.clone()),
+            Expression::Not(expression) => Some(Value::Bool(!is_truthy(expression, lookup))),
+            Expression::Equal(left, right) => Some(Value::Bool(
+                evaluate_text(left, lookup) == evaluate_text(right, lookup),
+            )),
+            Expression::NotEqual(left, right) => Some(Value::Bool(
+                evaluate_text(left, lookup) != evaluate_text(right, lookup),
+            )),
+            Expression::And(left, right) => Some(Value::Bool(
+                is_truthy(left, lookup) && is_truthy(right, lookup),
+            )),
+            Expression::Or(left, right) => Some(Value::Bool(
+                is_truthy(left, lookup) || is_truthy(right, lookup),
+            )),
+            Expression::Filter(expression, filter) => {
+                let value = expression.evaluate(lookup)?;
+                Some(filter.apply(value))
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn from_name(name: &str) -> Result<Filter, String> {
+        match name {
+            "safe" => Ok(Filter::Safe),
+            "lower" => Ok(Filter::Lower),
+            "upper" => Ok(Filter::Upper),
+            "length" => Ok(Filter::Length),
+            _ => Err(format!("Unknown filter `{}`", name)),
+        }
+    }
+
+    fn apply(&self, value: Value) -> Value {
+        match self {
+            Filter::Safe => Value::Html(value.to_string()),
+            Filter::Lower => Value::String(value.to_string().to_lowercase()),
+            Filter::Upper => Value::String(value.to_string().to_uppercase()),
+            Filter::Length => {
+                let length = match &value {
+                    Value::List(values) => values.len(),
+                    Value::Table(table) => table.len(),
+                    value => value.to_string().chars().count(),
+                };
+                Value::Integer(length as i64)
+            }
+        }
+    }
+}
+
+/// An undefined variable is false
+fn is_truthy(expression: &Expression, lookup: &dyn Fn(&[String]) -> Option<Value>) -> bool {
+    expression
+        .evaluate(lookup)
+        .is_some_and(|value| value.is_truthy())
+}
+
+/// Values are compared as text. An undefined variable is empty
+fn evaluate_text(expression: &Expression, lookup: &dyn Fn(&[String]) -> Option<Value>) -> String {
+    expression
+        .evaluate(lookup)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next_is(&self, token: &Token) -> bool {
+        self.tokens.get(self.position) == Some(token)
+    }
+
+    fn next_is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.position), Some(Token::Name(name)) if name == keyword)
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_and()?;
+        while self.next_is_keyword("or") {
+            self.position += 1;
+            expression = Expression::Or(Box::new(expression), Box::new(self.parse_and()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_not()?;
+        while self.next_is_keyword("and") {
+            self.position += 1;
+            expression = Expression::And(Box::new(expression), Box::new(self.parse_not()?));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, String> {
+        if self.next_is_keyword("not") {
+            self.position += 1;
+            return Ok(Expression::Not(Box::new(self.parse_not()?)));
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression, String> {
+        let left = self.parse_filtered()?;
+
+        if self.next_is(&Token::Equal) {
+            self.position += 1;
+            let right = self.parse_filtered()?;
+            return Ok(Expression::Equal(Box::new(left), Box::new(right)));
+        }
+
+        if self.next_is(&Token::NotEqual) {
+            self.position += 1;
+            let right = self.parse_filtered()?;
+            return Ok(Expression::NotEqual(Box::new(left), Box::new(right)));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_filtered(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_atom()?;
+
+        while self.next_is(&Token::Pipe) {
+            self.position += 1;
+            let filter = match self.tokens.get(self.position) {
+                Some(Token::Name(name)) => Filter::from_name(name)?,
+                _ => return Err("Expected a filter name after `|`".to_string()),
+            };
+            self.position += 1;
+            expression = Expression::Filter(Box::new(expression), filter);
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_atom(&mut self) -> Result<Expression, String> {
+        let token = match self.tokens.get(self.position) {
+            Some(token) => token,
+            None => return Err("Expected a value".to_string()),
+        };
+        self.position += 1;
+
+        match token {
+            Token::String(text) => Ok(Expression::Literal(Value::String(text.clone()))),
+            Token::Integer(value) => Ok(Expression::Literal(Value::Integer(*value))),
+            Token::Name(name) if name == "true" => Ok(Expression::Literal(Value::Bool(true))),
+            Token::Name(name) if name == "false" => Ok(Expression::Literal(Value::Bool(false))),
+            Token::Name(name) if name == "none" => Ok(Expression::Literal(Value::Null)),
+            Token::Name(name) => {
+                let path: Vec<String> = name.split('.').map(str::to_string).collect();
+                if path.iter().any(String::is_empty) {
+                    return Err(format!("Invalid variable name `{}`", name));
+                }
+                Ok(Expression::Variable(path))
+            }
+            Token::Open => {
+                let expression = self.parse_or()?;
+                if !self.next_is(&Token::Close) {
+                    return Err("Expected `)`".to_string());
+                }
+                self.position += 1;
+                Ok(expression)
+            }
+            _ => Err("Expected a value".to_string()),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some(next) if next == c => break,
+                        Some(next) => value.push(next),
+                        None => return Err(format!("Unclosed string in `{}`", text)),
+                    }
+                }
+                tokens.push(Token::String(value));
+            }
+            '0'..='9' => {
+                let mut digits = String::new();
+                while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    digits.push(digit);
+                    chars.next();
+                }
+                let value = digits
+                    .parse()
+                    .map_err(|_| format!("Number `{}` is too large", digits))?;
+                tokens.push(Token::Integer(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&next) = chars
+                    .peek()
+                    .filter(|c| c.is_alphanumeric() || **c == '_' || **c == '.')
+                {
+                    name.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Name(name));
+            }
+            '=' | '!' => {
+                chars.next();
+                if chars.next() != Some('=') {
+                    return Err(format!("Expected `{}=` in `{}`", c, text));
+                }
+                tokens.push(if c == '=' {
+                    Token::Equal
+                } else {
+                    Token::NotEqual
+                });
+            }
+            '|' => {
+                chars.next();
+                tokens.push(Token::Pipe);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            c => return Err(format!("Unexpected `{}` in `{}`", c, text)),
+        }
+    }
+
+    Ok(tokens)
+}
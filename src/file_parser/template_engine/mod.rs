@@ -0,0 +1,762 @@
+mod expression;
+mod value;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use expression::Expression;
+pub use value::Value;
+
+/// A parsed template of the small template language of the pages:
+///
+/// - `{{ page.title }}` prints a value, escaped unless it is already HTML or
+///   is marked with `| safe`
+/// - `{% if %}`, `{% elif %}`, `{% else %}` and `{% endif %}` print a part if a value is true
+/// - `{% for tag in page.tags %}`, `{% else %}` and `{% endfor %}` print a part for each
+///   item of a list, with `loop.index`, `loop.first` and `loop.last`
+/// - `{% include "header.html" %}` prints a partial template
+/// - `{% extends "base.html" %}` renders the template as `base.html`, with the
+///   `{% block name %}`s of the template replacing the blocks of the same name.
+///   Anything outside of the blocks is left out
+/// - `{# ... #}` is a comment
+pub struct TemplateDocument {
+    /// The name of the template, for error messages
+    name: String,
+    nodes: Vec<Node>,
+    /// The name of the template it extends, with the line of `{% extends %}`
+    extends: Option<(String, usize)>,
+    /// The templates it extends, from its parent up to the base of the chain
+    parents: Vec<TemplateDocument>,
+}
+
+/// Where templates are looked up by name, in the first of the dirs that has them
+pub struct TemplateLoader {
+    dirs: Vec<PathBuf>,
+}
+
+enum Node {
+    Text(String),
+    Output {
+        expression: Expression,
+        /// The text of the expression, for error messages
+        source: String,
+        line: usize,
+    },
+    If {
+        /// The conditions of the `if` and `elif`s, with what they print
+        branches: Vec<(Expression, Vec<Node>)>,
+        otherwise: Vec<Node>,
+    },
+    For {
+        variable: String,
+        iterable: Expression,
+        source: String,
+        body: Vec<Node>,
+        /// What is printed for an empty list
+        otherwise: Vec<Node>,
+        line: usize,
+    },
+    Include {
+        name: String,
+        line: usize,
+    },
+    Block {
+        name: String,
+        body: Vec<Node>,
+        line: usize,
+    },
+}
+
+enum Token {
+    Text(String),
+    /// The text inside `{{ }}`, with its line
+    Output(String, usize),
+    /// The text inside `{% %}`, with its line
+    Tag(String, usize),
+}
+
+/// A `{% %}` tag that ends a block, split into its keyword and the rest
+struct EndTag {
+    keyword: String,
+    rest: String,
+    line: usize,
+}
+
+impl TemplateDocument {
+    /// Parse the `source` of a template. Errors are prefixed with `name` and the line
+    pub fn parse(name: &str, source: &str) -> Result<TemplateDocument, String> {
+        let mut parser = Parser {
+            name,
+            tokens: tokenize(name, source)?,
+            position: 0,
+            extends: None,
+        };
+
+        let (nodes, end_tag) = parser.parse_nodes(&[])?;
+        if let Some(end_tag) = end_tag {
+            return Err(parser.unexpected(&end_tag));
+        }
+
+        if let Some(line) = find_duplicate_block(&nodes, &mut vec![]) {
+            return Err(error(name, line, "Block is defined twice"));
+        }
+
+        Ok(TemplateDocument {
+            name: name.to_string(),
+            nodes,
+            extends: parser.extends,
+            parents: vec![],
+        })
+    }
+
+    /// Load the chain of templates that this one extends from `loader`
+    pub fn load_parents(&mut self, loader: &TemplateLoader) -> Result<(), String> {
+        let mut names = vec![self.name.clone()];
+        let mut extends = self.extends.clone();
+
+        while let Some((parent, line)) = extends {
+            let child = names[names.len() - 1].clone();
+            let (path, source) = loader
+                .load(&parent)
+                .map_err(|message| error(&child, line, &message))?;
+
+            let name = path.display().to_string();
+            if names.contains(&name) {
+                return Err(error(
+                    &child,
+                    line,
+                    &format!(
+                        "Template '{}' extends itself: {} -> {}",
+                        name,
+                        names.join(" -> "),
+                        name
+                    ),
+                ));
+            }
+
+            let document = TemplateDocument::parse(&name, &source)?;
+            extends = document.extends.clone();
+            names.push(name);
+            self.parents.push(document);
+        }
+
+        Ok(())
+    }
+
+    /// Render the template with the values of `variables`
+    pub fn render(
+        &self,
+        variables: BTreeMap<String, Value>,
+        loader: &TemplateLoader,
+    ) -> Result<String, String> {
+        let base = self.base();
+        let mut renderer = Renderer {
+            loader,
+            blocks: self.blocks(),
+            scopes: vec![variables],
+            includes: vec![self.name.clone()],
+        };
+
+        let mut result = String::new();
+        renderer.render_nodes(&base.name, &base.nodes, &mut result)?;
+
+        Ok(result)
+    }
+
+    /// Whether the template prints the variable at `path`, like `page.content`.
+    /// Partials are not looked into
+    pub fn prints(&self, path: &str) -> bool {
+        let path: Vec<&str> = path.split('.').collect();
+        nodes_print(&self.base().nodes, &path, &self.blocks())
+    }
+
+    /// The template at the base of the chain of extended templates, which is rendered
+    fn base(&self) -> &TemplateDocument {
+        self.parents.last().unwrap_or(self)
+    }
+
+    /// The blocks of the chain of templates by name, with the name of the template
+    /// that defines them. A block of a template replaces the one of its parent
+    fn blocks(&self) -> BTreeMap<&str, (&str, &[Node])> {
+        let mut blocks = BTreeMap::new();
+
+        for document in self.parents.iter().rev().chain(std::iter::once(self)) {
+            collect_blocks(&document.name, &document.nodes, &mut blocks);
+        }
+
+        blocks
+    }
+}
+
+impl TemplateLoader {
+    pub fn new(dirs: Vec<PathBuf>) -> TemplateLoader {
+        TemplateLoader { dirs }
+    }
+
+    /// Find the path of the template called `name` in the first dir that has it.
+    /// The name must be a relative path without `..`, so that only the files
+    /// in the dirs can be loaded
+    pub fn find(&self, name: &str) -> Result<PathBuf, String> {
+        let is_inside = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_inside {
+            return Err(format!(
+                "Template '{}' must be a relative path without `..`",
+                name
+            ));
+        }
+
+        self.dirs
+            .iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                let dirs: Vec<String> = self
+                    .dirs
+                    .iter()
+                    .map(|dir| format!("'{}'", dir.display()))
+                    .collect();
+                format!("Template '{}' is not found in {}", name, dirs.join(", "))
+            })
+    }
+
+    /// Find the template called `name` in the first dir that has it, and read it
+    fn load(&self, name: &str) -> Result<(PathBuf, String), String> {
+        let path = self.find(name)?;
+        let source = fs::read_to_string(&path)
+            .map_err(|error| format!("Fail to read template at '{}': {}", path.display(), error))?;
+
+        Ok((path, source))
+    }
+}
+
+fn nodes_print(nodes: &[Node], path: &[&str], blocks: &BTreeMap<&str, (&str, &[Node])>) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Output { expression, .. } => expression_prints(expression, path),
+        Node::If {
+            branches,
+            otherwise,
+        } => {
+            branches
+                .iter()
+                .any(|(_, nodes)| nodes_print(nodes, path, blocks))
+                || nodes_print(otherwise, path, blocks)
+        }
+        Node::For {
+            body, otherwise, ..
+        } => nodes_print(body, path, blocks) || nodes_print(otherwise, path, blocks),
+        Node::Block { name, body, .. } => {
+            let body = blocks
+                .get(name.as_str())
+                .map_or(&body[..], |(_, nodes)| nodes);
+            nodes_print(body, path, blocks)
+        }
+        Node::Text(_) | Node::Include { .. } => false,
+    })
+}
+
+/// Add the blocks in `nodes`, and the blocks inside them, to `blocks`
+fn collect_blocks<'a>(
+    name: &'a str,
+    nodes: &'a [Node],
+    blocks: &mut BTreeMap<&'a str, (&'a str, &'a [Node])>,
+) {
+    for node in nodes {
+        match node {
+            Node::Block {
+                name: block, body, ..
+            } => {
+                blocks.insert(block, (name, body));
+                collect_blocks(name, body, blocks);
+            }
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                for (_, nodes) in branches {
+                    collect_blocks(name, nodes, blocks);
+                }
+                collect_blocks(name, otherwise, blocks);
+            }
+            Node::For {
+                body, otherwise, ..
+            } => {
+                collect_blocks(name, body, blocks);
+                collect_blocks(name, otherwise, blocks);
+            }
+            Node::Text(_) | Node::Output { .. } | Node::Include { .. } => {}
+        }
+    }
+}
+
+/// Find the line of a block with the same name as an earlier one
+fn find_duplicate_block<'a>(nodes: &'a [Node], names: &mut Vec<&'a str>) -> Option<usize> {
+    for node in nodes {
+        let duplicate = match node {
+            Node::Block { name, body, line } => {
+                if names.contains(&name.as_str()) {
+                    return Some(*line);
+                }
+                names.push(name);
+                find_duplicate_block(body, names)
+            }
+            Node::If {
+                branches,
+                otherwise,
+            } => branches
+                .iter()
+                .map(|(_, nodes)| nodes)
+                .chain(std::iter::once(otherwise))
+                .find_map(|nodes| find_duplicate_block(nodes, names)),
+            Node::For {
+                body, otherwise, ..
+            } => {
+                find_duplicate_block(body, names).or_else(|| find_duplicate_block(otherwise, names))
+            }
+            Node::Text(_) | Node::Output { .. } | Node::Include { .. } => None,
+        };
+
+        if duplicate.is_some() {
+            return duplicate;
+        }
+    }
+
+    None
+}
+
+fn expression_prints(expression: &Expression, path: &[&str]) -> bool {
+    match expression {
+        Expression::Variable(variable) => {
+            variable.iter().map(String::as_str).eq(path.iter().copied())
+        }
+        Expression::Filter(expression, _) => expression_prints(expression, path),
+        _ => false,
+    }
+}
+
+fn error(name: &str, line: usize, message: &str) -> String {
+    format!("{}:{}: {}", name, line, message)
+}
+
+/// Split a template into text, `{{ }}` and `{% %}`, dropping `{# #}` comments
+fn tokenize(name: &str, source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = source;
+    let mut line = 1;
+
+    while let Some(start) = find_tag_start(rest) {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+            line += rest[..start].matches('\n').count();
+        }
+
+        let opening = &rest[start..start + 2];
+        let closing = match opening {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+
+        let inner_start = start + 2;
+        let inner_end = match rest[inner_start..].find(closing) {
+            Some(end) => inner_start + end,
+            None => {
+                return Err(error(
+                    name,
+                    line,
+                    &format!("`{}` is never closed with `{}`", opening, closing),
+                ))
+            }
+        };
+        let inner = rest[inner_start..inner_end].trim().to_string();
+
+        match opening {
+            "{{" => tokens.push(Token::Output(inner, line)),
+            "{%" => tokens.push(Token::Tag(inner, line)),
+            _ => {}
+        }
+
+        let end = inner_end + 2;
+        line += rest[start..end].matches('\n').count();
+        rest = &rest[end..];
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+
+    Ok(tokens)
+}
+
+/// Find the start of the next `{{`, `{%` or `{#`
+fn find_tag_start(text: &str) -> Option<usize> {
+    text.match_indices('{')
+        .map(|(index, _)| index)
+        .find(|&index| text[index + 1..].starts_with(['{', '%', '#']))
+}
+
+struct Parser<'a> {
+    name: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    /// The name of the template in `{% extends %}`, with its line
+    extends: Option<(String, usize)>,
+}
+
+impl Parser<'_> {
+    /// Parse nodes up to a tag with one of the `end_keywords`, and return them with
+    /// that tag. The tag is None if the template ends first
+    fn parse_nodes(
+        &mut self,
+        end_keywords: &[&str],
+    ) -> Result<(Vec<Node>, Option<EndTag>), String> {
+        let mut nodes = vec![];
+
+        while self.position < self.tokens.len() {
+            let token = &self.tokens[self.position];
+            self.position += 1;
+
+            match token {
+                Token::Text(text) => nodes.push(Node::Text(text.clone())),
+                Token::Output(source, line) => {
+                    let line = *line;
+                    let source = source.clone();
+                    nodes.push(Node::Output {
+                        expression: self.parse_expression(&source, line)?,
+                        source,
+                        line,
+                    });
+                }
+                Token::Tag(text, line) => {
+                    let line = *line;
+                    let (keyword, rest) = match text.split_once(char::is_whitespace) {
+                        Some((keyword, rest)) => (keyword.to_string(), rest.trim().to_string()),
+                        None => (text.clone(), String::new()),
+                    };
+
+                    match keyword.as_str() {
+                        keyword if end_keywords.contains(&keyword) => {
+                            return Ok((
+                                nodes,
+                                Some(EndTag {
+                                    keyword: keyword.to_string(),
+                                    rest,
+                                    line,
+                                }),
+                            ))
+                        }
+                        "if" => nodes.push(self.parse_if(&rest, line)?),
+                        "for" => nodes.push(self.parse_for(&rest, line)?),
+                        "include" => nodes.push(Node::Include {
+                            name: self.parse_string(&rest, line)?,
+                            line,
+                        }),
+                        "block" => nodes.push(self.parse_block(&rest, line)?),
+                        "extends" if !end_keywords.is_empty() => {
+                            return Err(error(
+                                self.name,
+                                line,
+                                "`{% extends %}` must be outside of other tags",
+                            ))
+                        }
+                        "extends" if self.extends.is_some() => {
+                            return Err(error(
+                                self.name,
+                                line,
+                                "A template can only have one `{% extends %}`",
+                            ))
+                        }
+                        "extends" => self.extends = Some((self.parse_string(&rest, line)?, line)),
+                        "elif" | "else" | "endif" | "endfor" | "endblock" => {
+                            return Err(self.unexpected(&EndTag {
+                                keyword,
+                                rest,
+                                line,
+                            }))
+                        }
+                        _ => {
+                            return Err(error(
+                                self.name,
+                                line,
+                                &format!("Unknown tag `{{% {} %}}`", keyword),
+                            ))
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok((nodes, None))
+    }
+
+    /// Parse an `{% if %}` block, after its opening tag
+    fn parse_if(&mut self, condition: &str, line: usize) -> Result<Node, String> {
+        let mut branches = vec![];
+        let mut condition = self.parse_expression(condition, line)?;
+        let mut otherwise = vec![];
+
+        loop {
+            let (nodes, end_tag) = self.parse_nodes(&["elif", "else", "endif"])?;
+            let end_tag = end_tag.ok_or_else(|| self.unclosed("if", "endif", line))?;
+            branches.push((condition, nodes));
+
+            match end_tag.keyword.as_str() {
+                "elif" => condition = self.parse_expression(&end_tag.rest, end_tag.line)?,
+                "else" => {
+                    let (nodes, end_tag) = self.parse_nodes(&["endif"])?;
+                    end_tag.ok_or_else(|| self.unclosed("if", "endif", line))?;
+                    otherwise = nodes;
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(Node::If {
+            branches,
+            otherwise,
+        })
+    }
+
+    /// Parse a `{% for variable in list %}` block, after its opening tag
+    fn parse_for(&mut self, text: &str, line: usize) -> Result<Node, String> {
+        let (variable, source) = text
+            .split_once(" in ")
+            .map(|(variable, source)| (variable.trim(), source.trim()))
+            .filter(|(variable, _)| {
+                !variable.is_empty() && variable.chars().all(|c| c.is_alphanumeric() || c == '_')
+            })
+            .ok_or_else(|| error(self.name, line, "Expected `{% for name in list %}`"))?;
+
+        let iterable = self.parse_expression(source, line)?;
+
+        let (body, end_tag) = self.parse_nodes(&["else", "endfor"])?;
+        let end_tag = end_tag.ok_or_else(|| self.unclosed("for", "endfor", line))?;
+
+        let mut otherwise = vec![];
+        if end_tag.keyword == "else" {
+            let (nodes, end_tag) = self.parse_nodes(&["endfor"])?;
+            end_tag.ok_or_else(|| self.unclosed("for", "endfor", line))?;
+            otherwise = nodes;
+        }
+
+        Ok(Node::For {
+            variable: variable.to_string(),
+            iterable,
+            source: source.to_string(),
+            body,
+            otherwise,
+            line,
+        })
+    }
+
+    /// Parse a `{% block name %}`, after its opening tag.
+    /// The `{% endblock %}` can repeat the name
+    fn parse_block(&mut self, name: &str, line: usize) -> Result<Node, String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(error(self.name, line, "Expected `{% block name %}`"));
+        }
+
+        let (body, end_tag) = self.parse_nodes(&["endblock"])?;
+        let end_tag = end_tag.ok_or_else(|| self.unclosed("block", "endblock", line))?;
+        if !(end_tag.rest.is_empty() || end_tag.rest == name) {
+            return Err(error(
+                self.name,
+                end_tag.line,
+                &format!(
+                    "`{{% endblock {} %}}` closes `{{% block {} %}}`",
+                    end_tag.rest, name
+                ),
+            ));
+        }
+
+        Ok(Node::Block {
+            name: name.to_string(),
+            body,
+            line,
+        })
+    }
+
+    fn parse_expression(&self, text: &str, line: usize) -> Result<Expression, String> {
+        Expression::parse(text).map_err(|message| error(self.name, line, &message))
+    }
+
+    /// Parse a quoted string, like the name of an included template
+    fn parse_string(&self, text: &str, line: usize) -> Result<String, String> {
+        match Expression::parse(text) {
+            Ok(Expression::Literal(Value::String(text))) => Ok(text),
+            _ => Err(error(
+                self.name,
+                line,
+                &format!("Expected a quoted name, found `{}`", text),
+            )),
+        }
+    }
+
+    fn unexpected(&self, end_tag: &EndTag) -> String {
+        error(
+            self.name,
+            end_tag.line,
+            &format!("Unexpected `{{% {} %}}`", end_tag.keyword),
+        )
+    }
+
+    fn unclosed(&self, keyword: &str, end_keyword: &str, line: usize) -> String {
+        error(
+            self.name,
+            line,
+            &format!(
+                "`{{% {} %}}` is never closed with `{{% {} %}}`",
+                keyword, end_keyword
+            ),
+        )
+    }
+}
+
+struct Renderer<'a> {
+    loader: &'a TemplateLoader,
+    /// The blocks that replace the blocks of the same name
+    blocks: BTreeMap<&'a str, (&'a str, &'a [Node])>,
+    /// The variables, from the outermost scope. A `for` loop adds a scope
+    scopes: Vec<BTreeMap<String, Value>>,
+    /// The names of the templates being rendered, to catch an include of itself
+    includes: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn render_nodes(
+        &mut self,
+        name: &str,
+        nodes: &[Node],
+        result: &mut String,
+    ) -> Result<(), String> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => *result += text,
+                Node::Output {
+                    expression,
+                    source,
+                    line,
+                } => {
+                    let value = self.evaluate(expression).ok_or_else(|| {
+                        error(name, *line, &format!("`{}` is not defined", source))
+                    })?;
+                    *result += &value.to_html();
+                }
+                Node::If {
+                    branches,
+                    otherwise,
+                } => {
+                    let branch = branches
+                        .iter()
+                        .find(|(condition, _)| {
+                            self.evaluate(condition)
+                                .is_some_and(|value| value.is_truthy())
+                        })
+                        .map_or(otherwise, |(_, nodes)| nodes);
+                    self.render_nodes(name, branch, result)?;
+                }
+                Node::For {
+                    variable,
+                    iterable,
+                    source,
+                    body,
+                    otherwise,
+                    line,
+                } => {
+                    let items = match self.evaluate(iterable) {
+                        Some(Value::List(items)) => items,
+                        Some(Value::Null) => vec![],
+                        Some(_) => {
+                            return Err(error(name, *line, &format!("`{}` is not a list", source)))
+                        }
+                        None => {
+                            return Err(error(name, *line, &format!("`{}` is not defined", source)))
+                        }
+                    };
+
+                    if items.is_empty() {
+                        self.render_nodes(name, otherwise, result)?;
+                    }
+
+                    let count = items.len();
+                    for (index, item) in items.into_iter().enumerate() {
+                        let mut loop_info = BTreeMap::new();
+                        loop_info.insert("index".to_string(), Value::Integer(index as i64 + 1));
+                        loop_info.insert("first".to_string(), Value::Bool(index == 0));
+                        loop_info.insert("last".to_string(), Value::Bool(index + 1 == count));
+
+                        let mut scope = BTreeMap::new();
+                        scope.insert(variable.clone(), item);
+                        scope.insert("loop".to_string(), Value::Table(loop_info));
+
+                        self.scopes.push(scope);
+                        let rendered = self.render_nodes(name, body, result);
+                        self.scopes.pop();
+                        rendered?;
+                    }
+                }
+                Node::Block {
+                    name: block, body, ..
+                } => {
+                    let (block_template, body) = self
+                        .blocks
+                        .get(block.as_str())
+                        .copied()
+                        .unwrap_or((name, body));
+                    self.render_nodes(block_template, body, result)?;
+                }
+                Node::Include {
+                    name: partial,
+                    line,
+                } => self
+                    .render_include(partial, result)
+                    .map_err(|message| error(name, *line, &message))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn render_include(&mut self, partial: &str, result: &mut String) -> Result<(), String> {
+        if self.includes.iter().any(|name| name == partial) {
+            return Err(format!(
+                "Partial '{}' includes itself: {} -> {}",
+                partial,
+                self.includes.join(" -> "),
+                partial
+            ));
+        }
+
+        let (path, source) = self.loader.load(partial)?;
+        let document = TemplateDocument::parse(&path.display().to_string(), &source)?;
+
+        self.includes.push(partial.to_string());
+        let rendered = self.render_nodes(&document.name, &document.nodes, result);
+        self.includes.pop();
+
+        rendered
+    }
+
+    /// Evaluate an expression with the variables of the innermost scope that has them
+    fn evaluate(&self, expression: &Expression) -> Option<Value> {
+        expression.evaluate(&|path: &[String]| {
+            let (first, rest) = path.split_first()?;
+            let mut value = self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(first))?;
+
+            for key in rest {
+                value = value.get(key)?;
+            }
+
+            Some(value.clone())
+        })
+    }
+}
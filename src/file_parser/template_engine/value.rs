@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::file_parser::{escape::escape_html, front_matter};
+
+/// A value that a template can print, test and loop over
+#[derive(Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    /// Text that is already HTML, and is printed without escaping
+    Html(String),
+    List(Vec<Value>),
+    Table(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Whether the value counts as true in an `{% if %}`.
+    /// Null, false, zero and empty values are false
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(value) => *value,
+            Value::Integer(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::String(value) | Value::Html(value) => !value.is_empty(),
+            Value::List(values) => !values.is_empty(),
+            Value::Table(table) => !table.is_empty(),
+        }
+    }
+
+    /// Get the value of a key of a table
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Table(table) => table.get(key),
+            _ => None,
+        }
+    }
+
+    /// Print the value into HTML, escaping it unless it is already HTML
+    pub fn to_html(&self) -> String {
+        match self {
+            Value::Html(html) => html.clone(),
+            value => escape_html(&value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    /// Print the value as plain text. Lists are joined with commas
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::String(value) | Value::Html(value) => write!(f, "{}", value),
+            Value::List(values) => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                write!(f, "{}", values.join(", "))
+            }
+            Value::Table(table) => {
+                let entries: Vec<String> = table
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{}", entries.join(", "))
+            }
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::String(text.to_string())
+    }
+}
+
+impl From<Option<&str>> for Value {
+    fn from(text: Option<&str>) -> Self {
+        text.map_or(Value::Null, Value::from)
+    }
+}
+
+impl From<&front_matter::Value> for Value {
+    fn from(value: &front_matter::Value) -> Self {
+        match value {
+            front_matter::Value::Null => Value::Null,
+            front_matter::Value::Bool(value) => Value::Bool(*value),
+            front_matter::Value::Integer(value) => Value::Integer(*value),
+            front_matter::Value::Float(value) => Value::Float(*value),
+            front_matter::Value::String(value) => Value::String(value.clone()),
+            front_matter::Value::List(values) => {
+                Value::List(values.iter().map(Value::from).collect())
+            }
+            front_matter::Value::Table(table) => Value::Table(
+                table
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
@@ -1,16 +1,26 @@
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
-    process,
 };
 
-use crate::cli::arg_parser::ArgParser;
-use crate::file_parser::source_file::SourceFile;
+use crate::error::{Operation, PaperError};
+use crate::file_parser::escape::escape_html;
+use crate::file_parser::front_matter::FrontMatter;
 use crate::file_parser::markdown_parser::MarkdownDocument;
+use crate::file_parser::source_file::SourceFile;
+use crate::file_parser::template_engine::{TemplateDocument, TemplateLoader, Value};
+use crate::site_config::SiteConfig;
+
+/// The dir of the templates that pages can pick with `layout` in their front matter,
+/// and that `{% extends %}` looks up
+const LAYOUTS_DIR: &str = "layouts";
+/// The dir of the partial templates that `{% include %}` looks up
+const PARTIALS_DIR: &str = "partials";
 
 pub enum TemplateState {
-    PARSED,
-    RAW,
+    Parsed,
+    Raw,
 }
 
 /**
@@ -18,53 +28,113 @@ A struct for parsing raw file to html
 
 ## Examples
 
-```rust
-let args = ArgsParser::new();
-let content = "This is a text";
+```no_run
+use std::path::PathBuf;
+
+use paper::{SiteConfig, SourceFile, Template};
+
+let config = SiteConfig::builder().build().unwrap();
+let source_file = SourceFile::new(&PathBuf::from("page.txt")).unwrap();
 
 let mut template = Template::new();
-template.parse(content, args);
+template.parse(&source_file, &config).unwrap();
 
 println!("{}", template.content());
 ```
 */
 pub struct Template {
+    /// The parsed template, that pages are rendered with
+    document: TemplateDocument,
     /// The content of the template
     /// Call parse() to consume the current content
     /// and generate a parsed content
     content: String,
     /// The current parsing state of the template
     state: TemplateState,
+    /// The title of the page, empty until parse() finds one
+    title: String,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template::new()
+    }
 }
 
 impl Template {
     /// Create a template with raw content
     pub fn new() -> Template {
         let content = include_str!("./asset/template.html").to_string();
-        return Template {
+        let document = TemplateDocument::parse("template.html", &content)
+            .expect("the built-in template is valid");
+
+        Template {
+            document,
             content,
-            state: TemplateState::RAW,
-        };
+            state: TemplateState::Raw,
+            title: String::new(),
+        }
     }
 
-    /// Get a reference to the template's state.
-    pub fn state(&self) -> &TemplateState {
-        &self.state
+    /// Create a template with the raw content of an HTML file.
+    /// The file, or a template it extends, must print `{{ page.content }}`
+    /// for the page to go in
+    pub fn from_file(path: &Path, config: &SiteConfig) -> Result<Template, PaperError> {
+        let content = fs::read_to_string(path)
+            .map_err(|error| PaperError::io(path, Operation::ReadFile, error))?;
+
+        let mut document = TemplateDocument::parse(&path.display().to_string(), &content)
+            .map_err(PaperError::template)?;
+        document
+            .load_parents(&layout_loader(config))
+            .map_err(PaperError::template)?;
+        if !document.prints("page.content") {
+            return Err(PaperError::template(format!(
+                "Template at '{}' never prints {{{{ page.content }}}}",
+                path.display()
+            )));
+        }
+
+        Ok(Template {
+            document,
+            content,
+            state: TemplateState::Raw,
+            title: String::new(),
+        })
     }
 
-    /// Replace the title inside content
-    fn set_title(&mut self, title: &str) {
-        self.content = self.content.replace("$TITLE", title);
+    /// Load the template of a page. A `layout` is looked up in the layouts dir
+    /// of the project, then of the theme. Without one, the page uses the template
+    /// of the config, then `layouts/default.html`, then the built-in template
+    pub fn load(layout: Option<&str>, config: &SiteConfig) -> Result<Template, PaperError> {
+        let layouts = layout_loader(config);
+
+        if let Some(layout) = layout {
+            let path = layouts
+                .find(&format!("{}.html", layout))
+                .map_err(PaperError::template)?;
+            return Template::from_file(&path, config);
+        }
+
+        if let Some(path) = config.template() {
+            return Template::from_file(path, config);
+        }
+
+        if let Ok(path) = layouts.find("default.html") {
+            return Template::from_file(&path, config);
+        }
+
+        Ok(Template::new())
     }
 
-    /// Replace the stylesheet url inside content
-    fn set_styleshet(&mut self, url: &str) {
-        self.content = self.content.replace("$STYLESHEET_URL", url);
+    /// Get a reference to the template's state.
+    pub fn state(&self) -> &TemplateState {
+        &self.state
     }
 
-    /// Replace the body inside content
-    fn set_body(&mut self, body: &str) {
-        self.content = self.content.replace("$BODY", body);
+    /// Get the title of the parsed page, which is empty if it has none
+    pub fn title(&self) -> &str {
+        self.title.as_str()
     }
 
     /// Get the current content
@@ -72,16 +142,35 @@ impl Template {
         self.content.as_str()
     }
 
-    pub fn parse(&mut self, source_file: &SourceFile, args: &ArgParser) {
-	if source_file.ext() == "txt" {
-	    self.parse_raw_text(source_file.content(), args);
-	} else if source_file.ext() == "md" {
-	    self.parse_markdown_text(source_file.content(), args);
-	}
+    /// Render a `.md` or `.txt` source file into the template.
+    /// Fails for a source file of any other extension
+    pub fn parse(
+        &mut self,
+        source_file: &SourceFile,
+        config: &SiteConfig,
+    ) -> Result<(), PaperError> {
+        let front_matter = source_file.front_matter();
+
+        if source_file.ext() == "txt" {
+            self.parse_raw_text(source_file.content(), front_matter, config)
+        } else if source_file.ext() == "md" {
+            self.parse_markdown_text(source_file.content(), front_matter, config)
+        } else {
+            Err(PaperError::InvalidInput {
+                path: PathBuf::from(source_file.file_name()),
+                message: "only .md and .txt files can be rendered into a page".to_string(),
+            })
+        }
     }
 
-    /// Parse the raw content into html content
-    fn parse_raw_text(&mut self, content: &str, args: &ArgParser) {
+    /// Parse the raw content into html content.
+    /// A title in the front matter overrides the title of the first line
+    fn parse_raw_text(
+        &mut self,
+        content: &str,
+        front_matter: &FrontMatter,
+        config: &SiteConfig,
+    ) -> Result<(), PaperError> {
         let mut body = String::from("");
         let mut title = String::from("");
         let mut blank_line_count = 0;
@@ -102,61 +191,173 @@ impl Template {
 
         // Corresponding actions if the source has a title or not
         if blank_line_count == 2 {
-            body = format!("<h1>{}</h1>{}", title, body);
+            body = format!("<h1>{}</h1>{}", escape_html(&title), body);
         } else {
             body = parse_body(&title) + &body;
             title.clear();
         }
 
-        // Update the raw content
-        self.set_title(&title);
-        self.set_body(&body);
-        self.set_styleshet(parse_stylesheet_url(args.stylesheet()).as_str());
-        self.state = TemplateState::PARSED;
+        let title = front_matter.title().unwrap_or(&title).to_string();
+        self.render(front_matter, title, body, String::new(), config)
+    }
+
+    /// Parse the Markdown content into html content. Without a title in the front matter,
+    /// the title is the first level-1 heading, or else the first heading
+    fn parse_markdown_text(
+        &mut self,
+        content: &str,
+        front_matter: &FrontMatter,
+        config: &SiteConfig,
+    ) -> Result<(), PaperError> {
+        let doc = MarkdownDocument::parse(content, config.markdown_options());
+        let body = doc.print();
+        let title = match front_matter.title() {
+            Some(title) => title.to_string(),
+            None => doc.title().unwrap_or_default(),
+        };
+
+        let mut table_of_contents = String::new();
+        if config.toc() {
+            table_of_contents = doc.table_of_contents();
+        }
+
+        self.render(front_matter, title, body, table_of_contents, config)
     }
 
-    fn parse_markdown_text(&mut self, content: &str, args: &ArgParser) {
-	let mut body = parse_markdown_to_html(content);
-	let mut title = String::from("");
+    /// Render the page into the template, replacing the content
+    fn render(
+        &mut self,
+        front_matter: &FrontMatter,
+        title: String,
+        body: String,
+        table_of_contents: String,
+        config: &SiteConfig,
+    ) -> Result<(), PaperError> {
+        let extra = front_matter
+            .extra()
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::from(value)))
+            .collect();
+        let tags = front_matter
+            .tags()
+            .iter()
+            .map(|tag| Value::from(tag.as_str()));
+
+        let mut page = BTreeMap::new();
+        page.insert("title".to_string(), Value::from(title.as_str()));
+        page.insert("date".to_string(), Value::from(front_matter.date()));
+        page.insert(
+            "description".to_string(),
+            Value::from(front_matter.description()),
+        );
+        page.insert("tags".to_string(), Value::List(tags.collect()));
+        page.insert("extra".to_string(), Value::Table(extra));
+        page.insert("content".to_string(), Value::Html(body));
+        page.insert("toc".to_string(), Value::Html(table_of_contents));
+
+        let stylesheets = config
+            .stylesheets()
+            .iter()
+            .map(|url| parse_stylesheet_url(url))
+            .collect::<Result<Vec<String>, PaperError>>()?;
+
+        let mut site = BTreeMap::new();
+        site.insert(
+            "stylesheet".to_string(),
+            Value::Html(stylesheets.join("\n")),
+        );
+        site.insert("title".to_string(), Value::from(config.site_title()));
+        site.insert("base_url".to_string(), Value::from(config.base_url()));
+
+        let mut variables = BTreeMap::new();
+        variables.insert("page".to_string(), Value::Table(page));
+        variables.insert("site".to_string(), Value::Table(site));
+
+        let loader = TemplateLoader::new(template_dirs(PARTIALS_DIR, config));
+        self.content = self
+            .document
+            .render(variables, &loader)
+            .map_err(PaperError::template)?;
+        self.title = title;
+        self.state = TemplateState::Parsed;
 
-	self.set_title(&title);
-	self.set_body(&body);
-	self.set_styleshet(parse_stylesheet_url(args.stylesheet()).as_str());
-	self.state = TemplateState::PARSED;
+        Ok(())
     }
 }
 
-fn parse_markdown_to_html(content: &str) -> String {
-    let mut doc = MarkdownDocument::new();
+/// The files that pages can be rendered with: the layouts and partials of
+/// the project and the theme, the template of the config and the stylesheet files
+pub fn template_files(config: &SiteConfig) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for dir in template_dirs(LAYOUTS_DIR, config)
+        .iter()
+        .chain(&template_dirs(PARTIALS_DIR, config))
+    {
+        list_files(dir, &mut files);
+    }
+
+    files.extend(config.template().cloned());
+    files.extend(
+        config
+            .stylesheets()
+            .iter()
+            .map(PathBuf::from)
+            .filter(|path| path.is_file()),
+    );
+
+    files
+}
 
-    for line in content.lines() {
-	doc.add_line_to_document(&line);
+/// Recursively push the files of `dir` into `files`, in order of their paths
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).collect(),
+        Err(_) => return,
+    };
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            list_files(&path, files);
+        } else {
+            files.push(path);
+        }
     }
+}
+
+/// Where layouts are looked up, in the project and then in the theme
+fn layout_loader(config: &SiteConfig) -> TemplateLoader {
+    TemplateLoader::new(template_dirs(LAYOUTS_DIR, config))
+}
 
-    doc.print()
+/// The dir called `dir_name` of the project, followed by the one of the theme
+fn template_dirs(dir_name: &str, config: &SiteConfig) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(dir_name)];
+    if let Some(theme) = config.theme() {
+        dirs.push(theme.join(dir_name));
+    }
+
+    dirs
 }
 
-/// parse the content to suitable html tags
+/// parse the content to suitable html tags, escaping it as plain text
 fn parse_body(content: &str) -> String {
-    format!("<p>{}</p>", content)
+    format!("<p>{}</p>", escape_html(content))
 }
 
 /// Parse stylesheet url to <style> or <link>
-fn parse_stylesheet_url(url: &str) -> String {
+fn parse_stylesheet_url(url: &str) -> Result<String, PaperError> {
     let path = PathBuf::from(url);
 
     if path.is_file() {
-        let content = fs::read_to_string(&path).unwrap_or_else(|error| {
-            println!(
-                "Fail to read content of stylesheet at '{}': {}",
-                path.display(),
-                error
-            );
-            process::exit(0);
-        });
-
-        format!("<style>{}</style>", content)
+        let content = fs::read_to_string(&path)
+            .map_err(|error| PaperError::io(&path, Operation::ReadFile, error))?;
+
+        Ok(format!("<style>{}</style>", content))
     } else {
-        format!("<link rel='stylesheet' href='{}' />", url)
+        Ok(format!(
+            "<link rel=\"stylesheet\" href=\"{}\" />",
+            escape_html(url)
+        ))
     }
 }
@@ -0,0 +1,27 @@
+pub mod cli;
+pub mod diagnostic;
+pub mod error;
+pub mod file_parser;
+pub mod site_config;
+
+pub use cli::generator::Generator;
+pub use diagnostic::{Diagnostic, Severity};
+pub use error::PaperError;
+pub use file_parser::markdown_parser::{MarkdownDocument, MarkdownOptions};
+pub use file_parser::source_file::SourceFile;
+pub use file_parser::template_file::Template;
+pub use site_config::{SiteConfig, SiteConfigBuilder};
+
+/// Render a Markdown text into HTML
+pub fn render_markdown(content: &str, options: MarkdownOptions) -> String {
+    MarkdownDocument::parse(content, options).print()
+}
+
+/// Render a `.md` or `.txt` source file into a whole page, with the template
+/// it would get in a build of the site of `config`
+pub fn render_page(source_file: &SourceFile, config: &SiteConfig) -> Result<String, PaperError> {
+    let mut template = Template::load(source_file.front_matter().layout(), config)?;
+    template.parse(source_file, config)?;
+
+    Ok(template.content().to_string())
+}
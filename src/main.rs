@@ -1,10 +1,45 @@
-mod cli;
-mod file_parser;
+mod arg_parser;
 
-use cli::generator::Generator;
+use std::process;
+
+use arg_parser::ArgParser;
+use paper::{Diagnostic, Generator, PaperError, Severity};
 
 fn main() {
-    let generator = Generator::new();
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        process::exit(error.exit_code());
+    }
+}
+
+/// Parse the arguments and generate the site
+fn run() -> Result<(), PaperError> {
+    let config = ArgParser::new()?.into_config();
+    let mut generator = Generator::with_config(config);
+
+    let result = generator.run();
+    report(generator.diagnostics());
+
+    result
+}
+
+/// Print the diagnostics of a build, followed by a count of them
+fn report(diagnostics: &[Diagnostic]) {
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostic);
+    }
 
-    generator.run();
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+        .count();
+    eprintln!(
+        "{} error(s), {} warning(s)",
+        errors,
+        diagnostics.len() - errors
+    );
 }
@@ -0,0 +1,320 @@
+use std::path::{Path, PathBuf};
+
+use glob::Pattern;
+
+use crate::error::PaperError;
+use crate::file_parser::{
+    front_matter::{check_slug, FrontMatter},
+    markdown_parser::MarkdownOptions,
+};
+
+/// The dir of the stylesheet and other static files, in a project or a theme
+pub const ASSETS_DIR: &str = "asset";
+
+/**
+The options of a site build
+
+## Examples
+
+```no_run
+use paper::{Generator, SiteConfig};
+
+let config = SiteConfig::builder()
+    .input_path("content")
+    .dist_dir("public")
+    .site_title("My site")
+    .build()
+    .unwrap();
+
+Generator::with_config(config).run().unwrap();
+```
+*/
+pub struct SiteConfig {
+    input_paths: Vec<PathBuf>,
+    dist_dir: PathBuf,
+    stylesheets: Vec<String>,
+    template: Option<PathBuf>,
+    theme: Option<PathBuf>,
+    base_url: Option<String>,
+    site_title: Option<String>,
+    ignore: Vec<Pattern>,
+    /// The default front matter of the pages in a dir, from the innermost dir
+    defaults: Vec<(PathBuf, FrontMatter)>,
+    markdown: MarkdownOptions,
+    toc: bool,
+    fail_fast: bool,
+}
+
+/// Builds a `SiteConfig`. The options that are not set are the same as
+/// the ones of the CLI without flags
+pub struct SiteConfigBuilder {
+    input_paths: Vec<PathBuf>,
+    dist_dir: Option<PathBuf>,
+    stylesheets: Vec<String>,
+    template: Option<PathBuf>,
+    theme: Option<PathBuf>,
+    base_url: Option<String>,
+    site_title: Option<String>,
+    ignore: Vec<String>,
+    defaults: Vec<(PathBuf, FrontMatter)>,
+    markdown: MarkdownOptions,
+    toc: bool,
+    fail_fast: bool,
+}
+
+impl SiteConfig {
+    pub fn builder() -> SiteConfigBuilder {
+        SiteConfigBuilder::new()
+    }
+
+    /// Get a reference to the site config's input paths.
+    pub fn input_paths(&self) -> &[PathBuf] {
+        &self.input_paths
+    }
+
+    /// Get a reference to the site config's dist dir.
+    pub fn dist_dir(&self) -> &PathBuf {
+        &self.dist_dir
+    }
+
+    /// Get a reference to the site config's stylesheets.
+    pub fn stylesheets(&self) -> &[String] {
+        &self.stylesheets
+    }
+
+    /// Get a reference to the site config's template path.
+    pub fn template(&self) -> Option<&PathBuf> {
+        self.template.as_ref()
+    }
+
+    /// Get a reference to the site config's theme dir.
+    pub fn theme(&self) -> Option<&PathBuf> {
+        self.theme.as_ref()
+    }
+
+    /// The URL the site is served from, without a trailing slash
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// The title of the whole site
+    pub fn site_title(&self) -> Option<&str> {
+        self.site_title.as_deref()
+    }
+
+    /// Whether `path` matches one of the ignore patterns
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let path = normalize_path(path);
+        self.ignore.iter().any(|pattern| pattern.matches_path(path))
+    }
+
+    /// The default front matter of the dirs that `path` is in, from the innermost dir
+    pub fn page_defaults<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a FrontMatter> {
+        self.defaults
+            .iter()
+            .filter(move |(dir, _)| normalize_path(path).starts_with(dir))
+            .map(|(_, front_matter)| front_matter)
+    }
+
+    /// How Markdown pages are rendered
+    pub fn markdown_options(&self) -> MarkdownOptions {
+        self.markdown
+    }
+
+    /// Whether Markdown pages get a table of contents
+    pub fn toc(&self) -> bool {
+        self.toc
+    }
+
+    /// Whether the build stops at the first page that fails
+    pub fn fail_fast(&self) -> bool {
+        self.fail_fast
+    }
+
+    /// The options that change how pages are rendered, as text,
+    /// for a build to tell whether its pages are out of date
+    pub(crate) fn render_options(&self) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?} {:?} {}",
+            self.stylesheets,
+            self.template,
+            self.theme,
+            self.base_url,
+            self.site_title,
+            self.defaults,
+            self.markdown,
+            self.toc
+        )
+    }
+}
+
+impl Default for SiteConfigBuilder {
+    fn default() -> Self {
+        SiteConfigBuilder::new()
+    }
+}
+
+impl SiteConfigBuilder {
+    pub fn new() -> SiteConfigBuilder {
+        SiteConfigBuilder {
+            input_paths: vec![],
+            dist_dir: None,
+            stylesheets: vec![],
+            template: None,
+            theme: None,
+            base_url: None,
+            site_title: None,
+            ignore: vec![],
+            defaults: vec![],
+            markdown: MarkdownOptions {
+                heading_ids: true,
+                ..MarkdownOptions::default()
+            },
+            toc: false,
+            fail_fast: false,
+        }
+    }
+
+    /// Add a file or dir to generate pages from
+    pub fn input_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.input_paths.push(path.into());
+        self
+    }
+
+    /// Set the dir the pages are generated into, `./dist` by default
+    pub fn dist_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dist_dir = Some(path.into());
+        self
+    }
+
+    /// Add a stylesheet's URL, or a CSS file to put inside the pages. Without one,
+    /// the pages use `asset/style.css` of the project, or else of the theme
+    pub fn stylesheet(mut self, url: impl Into<String>) -> Self {
+        self.stylesheets.push(url.into());
+        self
+    }
+
+    /// Set the template of the pages without a layout
+    pub fn template(mut self, path: impl Into<PathBuf>) -> Self {
+        self.template = Some(path.into());
+        self
+    }
+
+    /// Set the theme dir, with layouts, partials and assets
+    pub fn theme(mut self, path: impl Into<PathBuf>) -> Self {
+        self.theme = Some(path.into());
+        self
+    }
+
+    /// Set the URL the site is served from
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = Some(url.into());
+        self
+    }
+
+    /// Set the title of the whole site
+    pub fn site_title(mut self, title: impl Into<String>) -> Self {
+        self.site_title = Some(title.into());
+        self
+    }
+
+    /// Add a glob pattern of the input paths to skip
+    pub fn ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore.push(pattern.into());
+        self
+    }
+
+    /// Set the front matter that the pages in `dir` get, for the keys they don't set
+    pub fn page_defaults(mut self, dir: impl Into<PathBuf>, front_matter: FrontMatter) -> Self {
+        self.defaults.push((dir.into(), front_matter));
+        self
+    }
+
+    /// Set how Markdown pages are rendered
+    pub fn markdown(mut self, options: MarkdownOptions) -> Self {
+        self.markdown = options;
+        self
+    }
+
+    /// Set whether Markdown pages get a table of contents
+    pub fn toc(mut self, toc: bool) -> Self {
+        self.toc = toc;
+        self
+    }
+
+    /// Set whether the build stops at the first page that fails, instead of
+    /// reporting it and going on with the other pages
+    pub fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Build the config. Fails on an invalid ignore pattern, or a default slug
+    /// that is not a file name
+    pub fn build(self) -> Result<SiteConfig, PaperError> {
+        let ignore = self
+            .ignore
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|error| PaperError::Config {
+                    message: format!("Invalid pattern `{}`: {}", pattern, error),
+                })
+            })
+            .collect::<Result<Vec<Pattern>, PaperError>>()?;
+
+        for (dir, front_matter) in &self.defaults {
+            if let Some(slug) = front_matter.slug() {
+                check_slug(slug).map_err(|message| PaperError::Config {
+                    message: format!("Defaults of '{}': {}", dir.display(), message),
+                })?;
+            }
+        }
+
+        let mut defaults: Vec<(PathBuf, FrontMatter)> = self
+            .defaults
+            .into_iter()
+            .map(|(dir, front_matter)| (normalize_path(&dir).to_path_buf(), front_matter))
+            .collect();
+        defaults.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+
+        let mut stylesheets = self.stylesheets;
+        if stylesheets.is_empty() {
+            stylesheets.push(default_stylesheet(self.theme.as_deref()));
+        }
+
+        Ok(SiteConfig {
+            input_paths: self.input_paths,
+            dist_dir: self.dist_dir.unwrap_or_else(|| PathBuf::from("./dist")),
+            stylesheets,
+            template: self.template,
+            theme: self.theme,
+            base_url: self
+                .base_url
+                .map(|url| url.trim_end_matches('/').to_string()),
+            site_title: self.site_title,
+            ignore,
+            defaults,
+            markdown: self.markdown,
+            toc: self.toc,
+            fail_fast: self.fail_fast,
+        })
+    }
+}
+
+/// The `style.css` of the project's asset dir, or else of the theme's
+fn default_stylesheet(theme: Option<&Path>) -> String {
+    let url = Path::new(ASSETS_DIR).join("style.css");
+    if let Some(theme) = theme {
+        let theme_url = theme.join(&url);
+        if !url.is_file() && theme_url.is_file() {
+            return theme_url.display().to_string();
+        }
+    }
+
+    url.display().to_string()
+}
+
+/// Drop a leading `./`, so that paths given as `./dir` and `dir` are the same
+fn normalize_path(path: &Path) -> &Path {
+    path.strip_prefix(".").unwrap_or(path)
+}
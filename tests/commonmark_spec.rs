@@ -0,0 +1,99 @@
+//! Runs the examples of the CommonMark spec (https://spec.commonmark.org/0.31.2/)
+//! against `render_markdown`, and reports how many pass in each section.
+//!
+//! Run with `cargo test --test commonmark_spec -- --nocapture` to see the report
+//! when every example passes.
+
+use paper::{render_markdown, MarkdownOptions};
+use serde_json::Value;
+
+const SPEC: &str = include_str!("fixtures/commonmark_spec.json");
+
+/// An example of the spec, with the HTML it should render to
+struct Example {
+    number: u64,
+    section: String,
+    markdown: String,
+    html: String,
+}
+
+/// The number of passed and total examples of a section
+struct SectionReport {
+    section: String,
+    passed: usize,
+    total: usize,
+}
+
+fn load_examples() -> Vec<Example> {
+    let examples: Vec<Value> = serde_json::from_str(SPEC).expect("spec fixture is valid JSON");
+
+    examples
+        .iter()
+        .map(|example| Example {
+            number: example["example"].as_u64().expect("example has a number"),
+            section: example["section"].as_str().unwrap_or_default().to_string(),
+            markdown: example["markdown"].as_str().unwrap_or_default().to_string(),
+            html: example["html"].as_str().unwrap_or_default().to_string(),
+        })
+        .collect()
+}
+
+#[test]
+fn commonmark_spec_examples() {
+    let mut reports: Vec<SectionReport> = vec![];
+    let mut failures = vec![];
+
+    for example in load_examples() {
+        let html = render_markdown(&example.markdown, MarkdownOptions::default());
+        let passed = html == example.html;
+
+        match reports.last_mut() {
+            Some(report) if report.section == example.section => {}
+            _ => reports.push(SectionReport {
+                section: example.section.clone(),
+                passed: 0,
+                total: 0,
+            }),
+        }
+
+        let report = reports.last_mut().unwrap();
+        report.total += 1;
+
+        if passed {
+            report.passed += 1;
+        } else {
+            failures.push(format!(
+                "example {} ({})\n  markdown: {:?}\n  expected: {:?}\n  actual:   {:?}",
+                example.number, example.section, example.markdown, example.html, html
+            ));
+        }
+    }
+
+    let mut passed = 0;
+    let mut total = 0;
+
+    for report in reports.iter() {
+        println!(
+            "{:<45} {:>3}/{:<3} {}",
+            report.section,
+            report.passed,
+            report.total,
+            if report.passed == report.total {
+                "pass"
+            } else {
+                "FAIL"
+            }
+        );
+        passed += report.passed;
+        total += report.total;
+    }
+    println!("{:<45} {:>3}/{:<3}", "Total", passed, total);
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} spec examples failed:\n\n{}",
+        failures.len(),
+        total,
+        failures.join("\n\n")
+    );
+}
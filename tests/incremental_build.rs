@@ -0,0 +1,122 @@
+//! Builds sites into a dist dir, and again after an edit, the way a writer does.
+//! The builds run in a temp dir that the tests change into, one test at a time.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use paper::{Generator, SiteConfig};
+
+/// Held by a test while it runs in its own project dir
+static CURRENT_DIR: Mutex<()> = Mutex::new(());
+
+/// Change into a new empty project dir under the temp dir, for the length of a test
+fn project_dir(name: &str) -> MutexGuard<'static, ()> {
+    let guard = CURRENT_DIR
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+
+    let dir = std::env::temp_dir().join(format!("paper-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("project")).unwrap();
+    std::env::set_current_dir(dir.join("project")).unwrap();
+
+    guard
+}
+
+fn build() {
+    let config = SiteConfig::builder()
+        .input_path("content")
+        .dist_dir("dist")
+        .stylesheet("https://example.com/style.css")
+        .build()
+        .unwrap();
+
+    Generator::with_config(config).run().unwrap();
+}
+
+fn write(path: &str, text: &str) {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).unwrap();
+    }
+    fs::write(path, text).unwrap();
+}
+
+fn read(path: &str) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn rebuilds_only_what_changed() {
+    let _guard = project_dir("incremental");
+
+    write("content/a.md", "# A");
+    write("content/b.md", "# B");
+    write("content/c.md", "# C");
+    build();
+
+    // an unchanged page is not written again
+    write("dist/content/a.html", "kept");
+    write("content/b.md", "# B edited");
+    // the page of a deleted source is removed
+    fs::remove_file("content/c.md").unwrap();
+    build();
+
+    assert_eq!(read("dist/content/a.html"), "kept");
+    assert!(read("dist/content/b.html").contains("B edited"));
+    assert!(!Path::new("dist/content/c.html").exists());
+    assert!(!read("dist/index.html").contains("c.html"));
+
+    // a template change rebuilds every page
+    write("layouts/default.html", "<main>{{ page.content }}</main>");
+    build();
+
+    assert!(read("dist/content/a.html").starts_with("<main><h1"));
+    assert!(read("dist/content/b.html").starts_with("<main><h1"));
+
+    // the paths of a hand-edited cache do not lead outside of the dist dir
+    write("../outside.html", "not a page");
+    let cache = read("dist/.paper-cache")
+        .replace("path = \"content/a.html\"", "path = \"../../outside.html\"");
+    write("dist/.paper-cache", &cache);
+    fs::remove_file("content/a.md").unwrap();
+    build();
+
+    assert_eq!(read("../outside.html"), "not a page");
+}
+
+#[cfg(unix)]
+#[test]
+fn builds_pages_whose_path_is_not_utf8() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let _guard = project_dir("non-utf8");
+    let dir = Path::new(OsStr::from_bytes(b"content/\xff"));
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("a.md"), "# A").unwrap();
+    write("content/b.md", "# B");
+    build();
+
+    // the page is generated again, as it is left out of the cache
+    let page = Path::new("dist").join(dir).join("a.html");
+    fs::write(&page, "old").unwrap();
+    build();
+
+    assert!(fs::read_to_string(&page).unwrap().contains("<h1"));
+    assert!(read("dist/.paper-cache").contains("content/b.md"));
+}
+
+#[test]
+fn skips_files_that_are_not_pages() {
+    let _guard = project_dir("not-pages");
+    write("content/a.md", "# A");
+    write("content/pic.png", "{{ page.content }}");
+    fs::write("content/logo.png", b"\x89PNG\r\n\x1a\n\xff\xfe").unwrap();
+    build();
+
+    assert!(Path::new("dist/content/a.html").is_file());
+    assert!(!Path::new("dist/content/pic.html").exists());
+    assert!(!Path::new("dist/content/logo.html").exists());
+    assert!(!read("dist/index.html").contains("pic"));
+}
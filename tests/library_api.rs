@@ -0,0 +1,124 @@
+//! Builds pages through the public API of the crate, the way other Rust tools would.
+
+use std::path::{Path, PathBuf};
+
+use paper::file_parser::template_engine::TemplateLoader;
+use paper::{
+    render_markdown, render_page, Diagnostic, MarkdownDocument, MarkdownOptions, PaperError,
+    Severity, SiteConfig, SourceFile,
+};
+
+#[test]
+fn renders_markdown_with_options() {
+    let options = MarkdownOptions {
+        strikethrough: true,
+        ..MarkdownOptions::default()
+    };
+
+    assert_eq!(
+        render_markdown("~~old~~ *new*", options),
+        "<p><del>old</del> <em>new</em></p>\n"
+    );
+}
+
+#[test]
+fn renders_a_page_into_the_built_in_template() {
+    let config = SiteConfig::builder()
+        .stylesheet("https://example.com/style.css")
+        .site_title("Site")
+        .build()
+        .unwrap();
+    let source_file = SourceFile::from_content(
+        Path::new("page.md"),
+        "---\ndescription: A <page>\n---\n# Hello\n\nText\n",
+    )
+    .unwrap();
+
+    let page = render_page(&source_file, &config).unwrap();
+
+    assert!(page.contains("<title>Hello | Site</title>"));
+    assert!(page.contains("<meta name=\"description\" content=\"A &lt;page&gt;\" />"));
+    assert!(page.contains("<link rel=\"stylesheet\" href=\"https://example.com/style.css\" />"));
+    assert!(page.contains("<h1 id=\"hello\">Hello</h1>\n<p>Text</p>"));
+}
+
+#[test]
+fn reports_a_missing_layout_as_bad_input() {
+    let config = SiteConfig::builder().build().unwrap();
+    let source_file =
+        SourceFile::from_content(Path::new("page.md"), "---\nlayout: missing\n---\n# Hello\n")
+            .unwrap();
+
+    let error = render_page(&source_file, &config).unwrap_err();
+
+    assert!(matches!(error, PaperError::Template { .. }));
+    assert_eq!(error.exit_code(), paper::error::EXIT_BAD_INPUT);
+}
+
+#[test]
+fn does_not_render_a_file_that_is_not_a_page() {
+    let config = SiteConfig::builder().build().unwrap();
+    let source_file = SourceFile::from_content(Path::new("pic.png"), "{{ page.content }}").unwrap();
+
+    let error = render_page(&source_file, &config).unwrap_err();
+
+    assert!(matches!(error, PaperError::InvalidInput { .. }));
+}
+
+#[test]
+fn reports_the_position_of_invalid_front_matter() {
+    let error = SourceFile::from_content(Path::new("page.md"), "---\ntitle: [\n---\n# Hello\n")
+        .err()
+        .unwrap();
+
+    let diagnostic = Diagnostic::from_error(&error);
+
+    assert_eq!(diagnostic.severity(), Severity::Error);
+    assert_eq!(
+        diagnostic.to_string(),
+        "page.md:2:8: error: Invalid YAML front matter: title: invalid type: sequence, expected a string"
+    );
+}
+
+#[test]
+fn rejects_a_slug_that_is_not_a_file_name() {
+    for slug in [
+        "../../escaped",
+        "dir/page",
+        "dir\\page",
+        "/abs",
+        "..",
+        "\"\"",
+    ] {
+        let content = format!("---\ntitle: Page\nslug: {}\n---\n", slug);
+        let error = SourceFile::from_content(Path::new("page.md"), &content)
+            .err()
+            .unwrap();
+
+        assert_eq!(Diagnostic::from_error(&error).line(), Some(3), "{}", slug);
+    }
+}
+
+#[test]
+fn leaves_footnotes_and_empty_headings_out_of_the_title() {
+    let options = MarkdownOptions {
+        footnotes: true,
+        heading_ids: true,
+        ..MarkdownOptions::default()
+    };
+    let document = MarkdownDocument::parse("# \n\n# Intro[^1]\n\n[^1]: A note\n", options);
+
+    assert_eq!(document.title().as_deref(), Some("Intro"));
+    assert!(document.print().contains("<h1 id=\"intro\">"));
+    assert!(document.table_of_contents().contains(">Intro</a>"));
+}
+
+#[test]
+fn does_not_load_templates_outside_of_the_template_dirs() {
+    let loader = TemplateLoader::new(vec![PathBuf::from("layouts")]);
+
+    for name in ["../secret.html", "nested/../../secret.html", "/etc/hosts"] {
+        let error = loader.find(name).unwrap_err();
+        assert!(error.contains(name), "{}", error);
+    }
+}
@@ -0,0 +1,286 @@
+//! Renders the extensions of Markdown beyond CommonMark, each switched on alone.
+
+use paper::{render_markdown, MarkdownDocument, MarkdownOptions};
+
+/// The default options, with the changes of `set`
+fn options(set: impl FnOnce(&mut MarkdownOptions)) -> MarkdownOptions {
+    let mut options = MarkdownOptions::default();
+    set(&mut options);
+    options
+}
+
+/// Render `text` with pipe tables
+fn table(text: &str) -> String {
+    render_markdown(text, options(|options| options.tables = true))
+}
+
+#[test]
+fn gives_each_heading_a_unique_id() {
+    let html = render_markdown(
+        "# A {#x}\n# B {#x}\n# Intro\n# C {#intro}\n# x-1\n# x\n",
+        options(|options| options.heading_ids = true),
+    );
+
+    assert_eq!(
+        html,
+        "<h1 id=\"x\">A</h1>\n<h1 id=\"x-1\">B</h1>\n<h1 id=\"intro\">Intro</h1>\n\
+         <h1 id=\"intro-1\">C</h1>\n<h1 id=\"x-1-1\">x-1</h1>\n<h1 id=\"x-2\">x</h1>\n"
+    );
+}
+
+#[test]
+fn aligns_table_columns() {
+    let text = "| Left | Center | Right | None |\n\
+                |:-----|:------:|------:|------|\n\
+                | a | b | c | d |\n";
+
+    assert_eq!(
+        table(text),
+        "<table>\n<thead>\n<tr>\n\
+         <th style=\"text-align: left\">Left</th>\n\
+         <th style=\"text-align: center\">Center</th>\n\
+         <th style=\"text-align: right\">Right</th>\n\
+         <th>None</th>\n\
+         </tr>\n</thead>\n<tbody>\n<tr>\n\
+         <td style=\"text-align: left\">a</td>\n\
+         <td style=\"text-align: center\">b</td>\n\
+         <td style=\"text-align: right\">c</td>\n\
+         <td>d</td>\n\
+         </tr>\n</tbody>\n</table>\n"
+    );
+}
+
+#[test]
+fn keeps_escaped_pipes_in_table_cells() {
+    let html = table("| f\\|oo |\n| ------ |\n| b `\\|` az |\n| b **\\|** im |\n");
+
+    assert!(html.contains("<th>f|oo</th>"), "{}", html);
+    assert!(html.contains("<td>b <code>|</code> az</td>"), "{}", html);
+    assert!(
+        html.contains("<td>b <strong>|</strong> im</td>"),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn fits_table_rows_to_the_header() {
+    let html = table("| a | b |\n| --- | --- |\n| bar |\n| bar | baz | boo |\n");
+
+    assert!(
+        html.contains(
+            "<tbody>\n<tr>\n<td>bar</td>\n<td></td>\n</tr>\n\
+             <tr>\n<td>bar</td>\n<td>baz</td>\n</tr>\n</tbody>"
+        ),
+        "{}",
+        html
+    );
+}
+
+#[test]
+fn ends_a_table_at_a_blank_line_or_a_block_start() {
+    let quote = table("| abc | def |\n| --- | --- |\n| bar | baz |\n> bar\n");
+    assert!(
+        quote.ends_with("</table>\n<blockquote>\n<p>bar</p>\n</blockquote>\n"),
+        "{}",
+        quote
+    );
+
+    let paragraph = table("| abc | def |\n| --- | --- |\n| bar | baz |\nbar\n\nbar\n");
+    assert!(
+        paragraph
+            .ends_with("<tr>\n<td>bar</td>\n<td></td>\n</tr>\n</tbody>\n</table>\n<p>bar</p>\n"),
+        "{}",
+        paragraph
+    );
+}
+
+#[test]
+fn needs_a_delimiter_row_that_fits_the_header() {
+    assert_eq!(
+        table("| abc | def |\n| --- |\n| bar |\n"),
+        "<p>| abc | def |\n| --- |\n| bar |</p>\n"
+    );
+    assert_eq!(
+        table("| abc | def |\n| --- | --- |\n"),
+        "<table>\n<thead>\n<tr>\n<th>abc</th>\n<th>def</th>\n</tr>\n</thead>\n</table>\n"
+    );
+}
+
+#[test]
+fn leaves_tables_as_text_by_default() {
+    assert_eq!(
+        render_markdown("| a |\n| - |\n| b |\n", MarkdownOptions::default()),
+        "<p>| a |\n| - |\n| b |</p>\n"
+    );
+}
+
+#[test]
+fn strikes_through_text_between_one_or_two_tildes() {
+    assert_eq!(
+        render_markdown(
+            "~~old~~ ~one~ ~~~three~~~",
+            options(|options| options.strikethrough = true)
+        ),
+        "<p><del>old</del> <del>one</del> ~~~three~~~</p>\n"
+    );
+}
+
+#[test]
+fn renders_list_items_starting_with_a_box_as_tasks() {
+    assert_eq!(
+        render_markdown(
+            "- [ ] todo\n- [x] done\n- [X] Done\n- [ ]not\n\n1. [ ] first\n",
+            options(|options| options.tasklists = true)
+        ),
+        "<ul>\n\
+         <li><input type=\"checkbox\" disabled=\"\" /> todo</li>\n\
+         <li><input type=\"checkbox\" checked=\"\" disabled=\"\" /> done</li>\n\
+         <li><input type=\"checkbox\" checked=\"\" disabled=\"\" /> Done</li>\n\
+         <li>[ ]not</li>\n\
+         </ul>\n\
+         <ol>\n\
+         <li><input type=\"checkbox\" disabled=\"\" /> first</li>\n\
+         </ol>\n"
+    );
+}
+
+#[test]
+fn links_bare_urls_without_their_trailing_punctuation() {
+    let autolink = |text| render_markdown(text, options(|options| options.autolinks = true));
+
+    assert_eq!(
+        autolink("Visit www.example.com, or https://example.com/a_(b)?x=1."),
+        "<p>Visit <a href=\"http://www.example.com\">www.example.com</a>, or \
+         <a href=\"https://example.com/a_(b)?x=1\">https://example.com/a_(b)?x=1</a>.</p>\n"
+    );
+    assert_eq!(
+        autolink("(see www.example.com/q=(a)))"),
+        "<p>(see <a href=\"http://www.example.com/q=(a)\">www.example.com/q=(a)</a>))</p>\n"
+    );
+    assert_eq!(
+        autolink("www.example.com/search?q=commonmark&hl;"),
+        "<p><a href=\"http://www.example.com/search?q=commonmark\">\
+         www.example.com/search?q=commonmark</a>&amp;hl;</p>\n"
+    );
+    assert_eq!(
+        autolink("`www.example.com`, [www.a.com](https://b.com), wwwfoo.com and http://"),
+        "<p><code>www.example.com</code>, <a href=\"https://b.com\">www.a.com</a>, \
+         wwwfoo.com and http://</p>\n"
+    );
+}
+
+#[test]
+fn leaves_strikethrough_tasks_and_urls_as_text_by_default() {
+    assert_eq!(
+        render_markdown(
+            "~~old~~ www.example.com\n\n- [x] done\n",
+            MarkdownOptions::default()
+        ),
+        "<p>~~old~~ www.example.com</p>\n<ul>\n<li>[x] done</li>\n</ul>\n"
+    );
+}
+
+/// Render `text` with footnotes
+fn footnotes(text: &str) -> String {
+    render_markdown(text, options(|options| options.footnotes = true))
+}
+
+#[test]
+fn numbers_footnotes_in_order_of_first_reference() {
+    let html = footnotes("First[^b] then[^a] and again[^b].\n\n[^a]: Note A.\n[^b]: Note B.\n");
+
+    assert_eq!(
+        html,
+        "<p>First<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1\">1</a></sup> \
+         then<sup class=\"footnote-ref\"><a href=\"#fn-2\" id=\"fnref-2\">2</a></sup> \
+         and again<sup class=\"footnote-ref\"><a href=\"#fn-1\" id=\"fnref-1-2\">1</a></sup>.</p>\n\
+         <section class=\"footnotes\">\n<ol>\n\
+         <li id=\"fn-1\">\n\
+         <p>Note B. <a href=\"#fnref-1\" class=\"footnote-backref\">↩</a> \
+         <a href=\"#fnref-1-2\" class=\"footnote-backref\">↩<sup>2</sup></a></p>\n\
+         </li>\n\
+         <li id=\"fn-2\">\n\
+         <p>Note A. <a href=\"#fnref-2\" class=\"footnote-backref\">↩</a></p>\n\
+         </li>\n\
+         </ol>\n</section>\n"
+    );
+}
+
+#[test]
+fn drops_unreferenced_footnotes_and_keeps_undefined_ones_as_text() {
+    let html = footnotes("Missing[^nope] and[^a].\n\n[^a]: Used.\n[^unused]: Never used.\n");
+
+    assert!(html.starts_with("<p>Missing[^nope] and<sup"), "{}", html);
+    assert!(html.contains("Used."), "{}", html);
+    assert!(!html.contains("Never used"), "{}", html);
+
+    assert_eq!(footnotes("Text.\n\n[^a]: Note.\n"), "<p>Text.</p>\n");
+}
+
+#[test]
+fn gives_headings_an_id_from_their_text_or_a_custom_one() {
+    let html = render_markdown(
+        "# Hello, World!\n## Hello World\n## Hello World\n\
+         ### **Bold** `code` {#custom}\n# Not {#a b}\n",
+        options(|options| options.heading_ids = true),
+    );
+
+    assert_eq!(
+        html,
+        "<h1 id=\"hello-world\">Hello, World!</h1>\n\
+         <h2 id=\"hello-world-1\">Hello World</h2>\n\
+         <h2 id=\"hello-world-2\">Hello World</h2>\n\
+         <h3 id=\"custom\"><strong>Bold</strong> <code>code</code></h3>\n\
+         <h1 id=\"not-a-b\">Not {#a b}</h1>\n"
+    );
+    assert_eq!(
+        render_markdown("# Hello\n", MarkdownOptions::default()),
+        "<h1>Hello</h1>\n"
+    );
+}
+
+#[test]
+fn adds_an_anchor_link_after_headings() {
+    let html = render_markdown(
+        "## Usage {#use}\n",
+        options(|options| {
+            options.heading_ids = true;
+            options.heading_anchors = true;
+        }),
+    );
+
+    assert_eq!(
+        html,
+        "<h2 id=\"use\">Usage <a class=\"anchor\" href=\"#use\">¶</a></h2>\n"
+    );
+}
+
+#[test]
+fn nests_the_table_of_contents_by_heading_level() {
+    let toc = |text| {
+        MarkdownDocument::parse(text, options(|options| options.heading_ids = true))
+            .table_of_contents()
+    };
+
+    assert_eq!(
+        toc("# A\n### C\n## B\n#### D\n# E\n"),
+        "<nav class=\"toc\">\n<ul>\n\
+         <li><a href=\"#a\">A</a>\n<ul>\n\
+         <li><a href=\"#c\">C</a></li>\n\
+         <li><a href=\"#b\">B</a>\n<ul>\n\
+         <li><a href=\"#d\">D</a></li>\n\
+         </ul>\n</li>\n\
+         </ul>\n</li>\n\
+         <li><a href=\"#e\">E</a></li>\n\
+         </ul>\n</nav>\n"
+    );
+    assert_eq!(
+        toc("### Deep\n# Top\n"),
+        "<nav class=\"toc\">\n<ul>\n\
+         <li><a href=\"#deep\">Deep</a></li>\n\
+         <li><a href=\"#top\">Top</a></li>\n\
+         </ul>\n</nav>\n"
+    );
+    assert_eq!(toc("No headings\n"), "");
+}
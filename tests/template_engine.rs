@@ -0,0 +1,167 @@
+//! Renders templates of the template language of the pages.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use paper::file_parser::template_engine::{TemplateDocument, TemplateLoader, Value};
+use paper::{render_page, SiteConfig, SourceFile};
+
+/// A new empty dir under the temp dir, for the templates of a test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("paper-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn page(entries: Vec<(&str, Value)>) -> BTreeMap<String, Value> {
+    let page = entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+
+    let mut variables = BTreeMap::new();
+    variables.insert("page".to_string(), Value::Table(page));
+    variables
+}
+
+fn render(source: &str, variables: BTreeMap<String, Value>) -> Result<String, String> {
+    TemplateDocument::parse("test.html", source)?.render(variables, &TemplateLoader::new(vec![]))
+}
+
+#[test]
+fn escapes_values_unless_they_are_safe() {
+    let variables = || page(vec![("title", Value::from("<b>Tom & Jerry</b>"))]);
+
+    assert_eq!(
+        render("{{ page.title }}", variables()).unwrap(),
+        "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;"
+    );
+    assert_eq!(
+        render("{{ page.title | safe }}", variables()).unwrap(),
+        "<b>Tom & Jerry</b>"
+    );
+}
+
+#[test]
+fn loops_over_lists_with_loop_variables() {
+    let template = "{% for tag in page.tags %}{{ loop.index }}:{{ tag }}\
+        {% if loop.first %}(first){% endif %}{% if loop.last %}(last){% endif %} \
+        {% else %}no tags{% endfor %}";
+    let tags = Value::List(vec![Value::from("a"), Value::from("b"), Value::from("c")]);
+
+    assert_eq!(
+        render(template, page(vec![("tags", tags)])).unwrap(),
+        "1:a(first) 2:b 3:c(last) "
+    );
+    assert_eq!(
+        render(template, page(vec![("tags", Value::List(vec![]))])).unwrap(),
+        "no tags"
+    );
+}
+
+#[test]
+fn picks_the_first_true_branch() {
+    let template = "{% if page.draft %}draft{% elif page.date == \"2021\" %}old\
+        {% elif page.date %}dated{% else %}undated{% endif %}";
+    let date = |date: &str| page(vec![("date", Value::from(date))]);
+
+    assert_eq!(render(template, date("2021")).unwrap(), "old");
+    assert_eq!(render(template, date("2022")).unwrap(), "dated");
+    assert_eq!(render(template, page(vec![])).unwrap(), "undated");
+    assert_eq!(
+        render(template, page(vec![("draft", Value::Bool(true))])).unwrap(),
+        "draft"
+    );
+}
+
+#[test]
+fn gives_the_line_of_an_undefined_variable() {
+    let error = render("<html>\n<title>\n{{ page.nope }}\n</title>", page(vec![])).unwrap_err();
+
+    assert_eq!(error, "test.html:3: `page.nope` is not defined");
+}
+
+#[test]
+fn stops_an_include_cycle() {
+    let dir = temp_dir("include-cycle");
+    fs::write(dir.join("a.html"), "{% include \"b.html\" %}").unwrap();
+    fs::write(dir.join("b.html"), "{% include \"a.html\" %}").unwrap();
+
+    let error = TemplateDocument::parse("test.html", "{% include \"a.html\" %}")
+        .unwrap()
+        .render(page(vec![]), &TemplateLoader::new(vec![dir]))
+        .unwrap_err();
+
+    assert!(error.contains("includes itself"), "{}", error);
+}
+
+#[test]
+fn prints_page_content_literally() {
+    let config = SiteConfig::builder().build().unwrap();
+    let source_file = SourceFile::from_content(
+        Path::new("page.txt"),
+        "Title\n\n\nUse {{ page.title }} and $BODY",
+    )
+    .unwrap();
+
+    let page = render_page(&source_file, &config).unwrap();
+
+    assert!(page.contains("<p>Use {{ page.title }} and $BODY</p>"));
+}
+
+#[test]
+fn overrides_blocks_through_a_chain_of_layouts() {
+    let dir = temp_dir("extends");
+    fs::write(
+        dir.join("base.html"),
+        "<title>{% block title %}Base{% endblock %}</title>\
+         <main>{% block content %}{{ page.content }}{% endblock %}</main>\
+         <footer>{% block footer %}Footer{% endblock %}</footer>",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("post.html"),
+        "{% extends \"base.html\" %}\
+         {% block title %}Post{% endblock %}\
+         {% block footer %}Post footer{% endblock %}",
+    )
+    .unwrap();
+    let loader = TemplateLoader::new(vec![dir.clone()]);
+
+    let mut document = TemplateDocument::parse(
+        &dir.join("note.html").display().to_string(),
+        "{% extends \"post.html\" %}{% block title %}Note{% endblock %}",
+    )
+    .unwrap();
+    document.load_parents(&loader).unwrap();
+
+    assert_eq!(
+        document
+            .render(
+                page(vec![("content", Value::Html("<p>Hi</p>".to_string()))]),
+                &loader
+            )
+            .unwrap(),
+        "<title>Note</title><main><p>Hi</p></main><footer>Post footer</footer>"
+    );
+}
+
+#[test]
+fn stops_an_extends_cycle() {
+    let dir = temp_dir("extends-cycle");
+    fs::write(dir.join("a.html"), "{% extends \"b.html\" %}").unwrap();
+    fs::write(dir.join("b.html"), "{% extends \"a.html\" %}").unwrap();
+
+    let mut document = TemplateDocument::parse(
+        &dir.join("a.html").display().to_string(),
+        "{% extends \"b.html\" %}",
+    )
+    .unwrap();
+    let error = document
+        .load_parents(&TemplateLoader::new(vec![dir]))
+        .unwrap_err();
+
+    assert!(error.contains("extends itself"), "{}", error);
+}